@@ -0,0 +1,237 @@
+use crate::ParseError;
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+const MAX_HOURS: u64 = 838;
+const MAX_MINUTES: u64 = 59;
+const MAX_SECONDS: u64 = 59;
+const MAX_FSP: u8 = 6;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+const NANOS_PER_MICRO: u64 = 1000;
+const NANO_WIDTH: u32 = 9;
+
+/// Extract bits `hi..=lo` of `bits`.
+#[inline]
+fn field(bits: u64, hi: u32, lo: u32) -> u64 {
+    bits >> lo & ((1 << (hi - lo + 1)) - 1)
+}
+
+/// The fields of a TIME value packed into a `u64`, decoded from either of the two layouts in
+/// use:
+///
+/// * the nano layout of dfa and pull-4427: `neg` at bit 63, hour 62..53, minute 52..47, second
+///   46..41, nanoseconds 40..9, fsp 8..1 and bit 0 unused;
+/// * the micro layout of duration-nom: `neg` at bit 55, bit 54 unused, hour 53..44, minute
+///   43..38, second 37..32, microseconds 31..8, fsp 7..0 and bits 63..56 unused.
+///
+/// Decoding never trusts the bits: a field out of its range, a value beyond `838:59:59`, a
+/// fraction finer than `fsp` or a set unused bit is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeFields {
+    pub neg: bool,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+    pub nanos: u64,
+    pub fsp: u8,
+}
+
+impl TimeFields {
+    fn check(self) -> Result<Self> {
+        if self.hour > MAX_HOURS
+            || self.hour == MAX_HOURS
+                && self.minute == MAX_MINUTES
+                && self.second == MAX_SECONDS
+                && self.nanos > 0
+        {
+            return Err(ParseError::HourOutOfRange(0));
+        }
+        if self.minute > MAX_MINUTES {
+            return Err(ParseError::MinuteOutOfRange(0));
+        }
+        if self.second > MAX_SECONDS {
+            return Err(ParseError::SecondOutOfRange(0));
+        }
+        if self.nanos >= NANOS_PER_SEC {
+            return Err(ParseError::Overflow(0));
+        }
+        if self.fsp > MAX_FSP {
+            return Err(ParseError::InvalidFsp(self.fsp as i8));
+        }
+        if !self
+            .nanos
+            .is_multiple_of(10u64.pow(NANO_WIDTH - u32::from(self.fsp)))
+        {
+            return Err(ParseError::Overflow(0));
+        }
+        Ok(self)
+    }
+
+    pub fn from_nano_bits(bits: u64) -> Result<Self> {
+        let fields = TimeFields {
+            neg: field(bits, 63, 63) == 1,
+            hour: field(bits, 62, 53),
+            minute: field(bits, 52, 47),
+            second: field(bits, 46, 41),
+            nanos: field(bits, 40, 9),
+            fsp: field(bits, 8, 1) as u8,
+        }
+        .check()?;
+        if fields.to_nano_bits() != bits {
+            return Err(ParseError::Overflow(0));
+        }
+        Ok(fields)
+    }
+
+    pub fn to_nano_bits(self) -> u64 {
+        u64::from(self.neg) << 63
+            | self.hour << 53
+            | self.minute << 47
+            | self.second << 41
+            | self.nanos << 9
+            | u64::from(self.fsp) << 1
+    }
+
+    pub fn from_micro_bits(bits: u64) -> Result<Self> {
+        let fields = TimeFields {
+            neg: field(bits, 55, 55) == 1,
+            hour: field(bits, 53, 44),
+            minute: field(bits, 43, 38),
+            second: field(bits, 37, 32),
+            nanos: field(bits, 31, 8) * NANOS_PER_MICRO,
+            fsp: field(bits, 7, 0) as u8,
+        }
+        .check()?;
+        if fields.to_micro_bits()? != bits {
+            return Err(ParseError::Overflow(0));
+        }
+        Ok(fields)
+    }
+
+    /// Fails if the fraction is not a whole number of microseconds, which the micro layout
+    /// cannot hold.
+    pub fn to_micro_bits(self) -> Result<u64> {
+        if !self.nanos.is_multiple_of(NANOS_PER_MICRO) {
+            return Err(ParseError::Overflow(0));
+        }
+        Ok(u64::from(self.neg) << 55
+            | self.hour << 44
+            | self.minute << 38
+            | self.second << 32
+            | (self.nanos / NANOS_PER_MICRO) << 8
+            | u64::from(self.fsp))
+    }
+}
+
+/// Re-encode a value of the nano layout in the micro layout.
+pub fn nano_bits_to_micro_bits(bits: u64) -> Result<u64> {
+    TimeFields::from_nano_bits(bits)?.to_micro_bits()
+}
+
+/// Re-encode a value of the micro layout in the nano layout.
+pub fn micro_bits_to_nano_bits(bits: u64) -> Result<u64> {
+    Ok(TimeFields::from_micro_bits(bits)?.to_nano_bits())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts() {
+        let fields = TimeFields {
+            neg: true,
+            hour: 838,
+            minute: 59,
+            second: 58,
+            nanos: 999_999_000,
+            fsp: 6,
+        };
+        let nano_bits = fields.to_nano_bits();
+        let micro_bits = fields.to_micro_bits().unwrap();
+        assert_eq!(TimeFields::from_nano_bits(nano_bits), Ok(fields));
+        assert_eq!(TimeFields::from_micro_bits(micro_bits), Ok(fields));
+        assert_eq!(nano_bits_to_micro_bits(nano_bits), Ok(micro_bits));
+        assert_eq!(micro_bits_to_nano_bits(micro_bits), Ok(nano_bits));
+
+        let malformed = vec![
+            (
+                TimeFields {
+                    minute: 60,
+                    ..fields
+                },
+                ParseError::MinuteOutOfRange(0),
+            ),
+            (
+                TimeFields {
+                    second: 63,
+                    ..fields
+                },
+                ParseError::SecondOutOfRange(0),
+            ),
+            (
+                TimeFields {
+                    hour: 839,
+                    ..fields
+                },
+                ParseError::HourOutOfRange(0),
+            ),
+            (
+                TimeFields {
+                    second: 59,
+                    ..fields
+                },
+                ParseError::HourOutOfRange(0),
+            ),
+            (TimeFields { fsp: 7, ..fields }, ParseError::InvalidFsp(7)),
+        ];
+        for (fields, err) in malformed {
+            assert_eq!(TimeFields::from_nano_bits(fields.to_nano_bits()), Err(err));
+            let micro_bits = fields.to_micro_bits().unwrap();
+            assert_eq!(TimeFields::from_micro_bits(micro_bits), Err(err));
+        }
+
+        let too_many_nanos = TimeFields {
+            nanos: NANOS_PER_SEC,
+            ..fields
+        }
+        .to_nano_bits();
+        assert_eq!(
+            TimeFields::from_nano_bits(too_many_nanos),
+            Err(ParseError::Overflow(0))
+        );
+        let sub_micro = TimeFields { nanos: 1, ..fields }.to_nano_bits();
+        assert_eq!(
+            nano_bits_to_micro_bits(sub_micro),
+            Err(ParseError::Overflow(0))
+        );
+        // The fraction cannot be finer than `fsp`.
+        for (nanos, fsp) in [(500_000_000, 0), (10_000_000, 1), (999_999_000, 5)] {
+            let finer = TimeFields {
+                nanos,
+                fsp,
+                ..fields
+            };
+            assert_eq!(
+                TimeFields::from_nano_bits(finer.to_nano_bits()),
+                Err(ParseError::Overflow(0))
+            );
+            assert_eq!(
+                TimeFields::from_micro_bits(finer.to_micro_bits().unwrap()),
+                Err(ParseError::Overflow(0))
+            );
+        }
+        assert_eq!(
+            TimeFields::from_nano_bits(nano_bits | 1),
+            Err(ParseError::Overflow(0))
+        );
+        assert_eq!(
+            TimeFields::from_micro_bits(micro_bits | 1 << 54),
+            Err(ParseError::Overflow(0))
+        );
+        assert_eq!(
+            TimeFields::from_micro_bits(micro_bits | 1 << 60),
+            Err(ParseError::Overflow(0))
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::str;
+
+const NANO_WIDTH: usize = 9;
+// A sign, the twenty digits of `u64::MAX` hours, `:mm:ss` and a dot with nine fraction digits.
+const MAX_LEN: usize = 1 + 20 + 6 + 1 + NANO_WIDTH;
+
+/// A TIME value rendered the way MySQL prints it, `[-]hh:mm:ss[.fff]`, without allocating.
+///
+/// Hours take at least two digits and as many as they need beyond that, the fraction is cut to
+/// exactly `fsp` digits, and the zero value never gets a sign.
+#[derive(Clone, Copy)]
+pub struct TimeStr {
+    buf: [u8; MAX_LEN],
+    len: usize,
+}
+
+impl TimeStr {
+    /// `nanos` is the fraction in nanoseconds, `fsp` is capped at nine digits.
+    pub fn new(neg: bool, hour: u64, minute: u64, second: u64, nanos: u64, fsp: u8) -> Self {
+        let mut s = TimeStr {
+            buf: [0; MAX_LEN],
+            len: 0,
+        };
+        if neg && (hour != 0 || minute != 0 || second != 0 || nanos != 0) {
+            s.push(b'-');
+        }
+        s.push_digits(hour, 2);
+        s.push(b':');
+        s.push_digits(minute, 2);
+        s.push(b':');
+        s.push_digits(second, 2);
+        let fsp = (fsp as usize).min(NANO_WIDTH);
+        if fsp > 0 {
+            s.push(b'.');
+            s.push_digits(nanos / 10u64.pow((NANO_WIDTH - fsp) as u32), fsp);
+        }
+        s
+    }
+
+    #[inline]
+    fn push(&mut self, c: u8) {
+        self.buf[self.len] = c;
+        self.len += 1;
+    }
+
+    /// Push `value` zero padded to at least `width` digits.
+    fn push_digits(&mut self, mut value: u64, width: usize) {
+        let mut digits = [b'0'; 20];
+        let mut n = 0;
+        while value > 0 || n < width {
+            digits[n] = b'0' + (value % 10) as u8;
+            value /= 10;
+            n += 1;
+        }
+        for &d in digits[..n].iter().rev() {
+            self.push(d);
+        }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.as_bytes()) }
+    }
+}
+
+impl fmt::Display for TimeStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl fmt::Debug for TimeStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_str() {
+        let cases = vec![
+            ((false, 12, 34, 56, 0, 0), "12:34:56"),
+            ((true, 1, 2, 3, 120_000_000, 2), "-01:02:03.12"),
+            ((false, 838, 59, 59, 999_999_000, 6), "838:59:59.999999"),
+            ((true, 0, 0, 0, 0, 3), "00:00:00.000"),
+            ((true, 0, 0, 0, 500_000_000, 1), "-00:00:00.5"),
+            ((false, 0, 0, 1, 123_456_789, 9), "00:00:01.123456789"),
+            (
+                (false, u64::MAX, 59, 59, 999_999_999, 9),
+                "18446744073709551615:59:59.999999999",
+            ),
+        ];
+        for ((neg, hour, minute, second, nanos, fsp), expected) in cases {
+            let s = TimeStr::new(neg, hour, minute, second, nanos, fsp);
+            assert_eq!(s.as_str(), expected);
+        }
+        assert_eq!(
+            format!("{:>10}", TimeStr::new(false, 1, 2, 3, 0, 0)),
+            "  01:02:03"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::error::Error;
+use std::fmt;
+
+mod bits;
+mod format;
+mod literal;
+mod macros;
+mod number;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[doc(hidden)]
+pub mod testdata;
+
+pub use bits::{micro_bits_to_nano_bits, nano_bits_to_micro_bits, TimeFields};
+pub use format::TimeStr;
+pub use literal::{check_hour_with_mode, split_day, split_hhmmss, trim_input};
+pub use number::{check_date, split_time_number, Decimal, MAX_TIME_NUMBER};
+#[cfg(feature = "serde")]
+pub use serde_impl::{deserialize_time, serialize_time};
+// For `impl_serde!`, so that callers do not need to name the same serde.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
+
+/// The reason why a TIME literal was rejected.
+///
+/// Every variant that originates from the input carries the byte offset at which the parser
+/// gave up, so callers can point at the offending character when building an error message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidCharacter(usize),
+    HourOutOfRange(usize),
+    MinuteOutOfRange(usize),
+    SecondOutOfRange(usize),
+    InvalidFsp(i8),
+    EmptyInput,
+    DanglingColon(usize),
+    Overflow(usize),
+    InvalidDate(usize),
+}
+
+impl ParseError {
+    /// The byte offset where parsing failed. Errors that are not tied to a position of the
+    /// input (`InvalidFsp`, `EmptyInput`) report `0`.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        match *self {
+            ParseError::InvalidCharacter(offset)
+            | ParseError::HourOutOfRange(offset)
+            | ParseError::MinuteOutOfRange(offset)
+            | ParseError::SecondOutOfRange(offset)
+            | ParseError::DanglingColon(offset)
+            | ParseError::Overflow(offset)
+            | ParseError::InvalidDate(offset) => offset,
+            ParseError::InvalidFsp(_) | ParseError::EmptyInput => 0,
+        }
+    }
+
+    /// Move the error to `offset`, used by helpers that only see a slice of the input.
+    #[inline]
+    pub fn at(self, offset: usize) -> Self {
+        match self {
+            ParseError::InvalidCharacter(_) => ParseError::InvalidCharacter(offset),
+            ParseError::HourOutOfRange(_) => ParseError::HourOutOfRange(offset),
+            ParseError::MinuteOutOfRange(_) => ParseError::MinuteOutOfRange(offset),
+            ParseError::SecondOutOfRange(_) => ParseError::SecondOutOfRange(offset),
+            ParseError::DanglingColon(_) => ParseError::DanglingColon(offset),
+            ParseError::Overflow(_) => ParseError::Overflow(offset),
+            ParseError::InvalidDate(_) => ParseError::InvalidDate(offset),
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::InvalidCharacter(offset) => {
+                write!(f, "invalid character at offset {}", offset)
+            }
+            ParseError::HourOutOfRange(offset) => {
+                write!(f, "hour out of range at offset {}", offset)
+            }
+            ParseError::MinuteOutOfRange(offset) => {
+                write!(f, "minute out of range at offset {}", offset)
+            }
+            ParseError::SecondOutOfRange(offset) => {
+                write!(f, "second out of range at offset {}", offset)
+            }
+            ParseError::InvalidFsp(fsp) => write!(f, "invalid fsp {}", fsp),
+            ParseError::EmptyInput => write!(f, "empty input"),
+            ParseError::DanglingColon(offset) => write!(f, "dangling colon at offset {}", offset),
+            ParseError::Overflow(offset) => write!(f, "value overflow at offset {}", offset),
+            ParseError::InvalidDate(offset) => write!(f, "invalid date at offset {}", offset),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Run `parse` on the longest prefix of `s` it accepts and return the value along with the
+/// length of that prefix, so trailing garbage can be truncated like MySQL does for
+/// `'12:34:56abc'`.
+///
+/// Only `InvalidCharacter` and `DanglingColon` errors shorten the input, any other error is
+/// returned as is. The input is never shortened to the empty prefix, even if `parse` accepts it:
+/// when no non-empty prefix parses, or only a blank one, the error of the whole input is
+/// returned.
+pub fn parse_longest_prefix<T, F>(s: &[u8], mut parse: F) -> Result<(T, usize), ParseError>
+where
+    F: FnMut(&[u8]) -> Result<T, ParseError>,
+{
+    let mut len = s.len();
+    let mut first = None;
+    loop {
+        match parse(&s[..len]) {
+            Ok(value) => return Ok((value, len)),
+            Err(e @ ParseError::InvalidCharacter(_)) | Err(e @ ParseError::DanglingColon(_))
+                if len > 0 =>
+            {
+                let first = *first.get_or_insert(e);
+                len = e.offset().min(len - 1);
+                if len == 0 {
+                    return Err(first);
+                }
+            }
+            Err(ParseError::EmptyInput) => return Err(first.unwrap_or(ParseError::EmptyInput)),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// How a TIME value outside of `[-838:59:59, 838:59:59]` is treated, mirroring MySQL's strict
+/// and non-strict `sql_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Reject the value.
+    #[default]
+    Strict,
+    /// Clamp the value to the nearest bound of the range.
+    Truncate,
+}
+
+/// A condition noticed while evaluating a TIME value that does not make it fail, surfaced by
+/// the SQL layer as `SHOW WARNINGS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Warning {
+    /// Non-zero digits of the fraction beyond `fsp` were rounded away.
+    TruncatedFraction,
+    /// Trailing characters that are not part of the value were ignored.
+    TruncatedTrailing,
+    /// The value was clamped to `[-838:59:59, 838:59:59]`.
+    ClampedRange,
+    /// The date part of the input was the zero date.
+    ZeroDate,
+}
+
+impl Warning {
+    const COUNT: usize = 4;
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::TruncatedFraction => write!(f, "truncated fraction"),
+            Warning::TruncatedTrailing => write!(f, "truncated trailing characters"),
+            Warning::ClampedRange => write!(f, "value clamped to the TIME range"),
+            Warning::ZeroDate => write!(f, "zero date"),
+        }
+    }
+}
+
+/// The default cap of recorded warnings, the same as MySQL's `max_error_count`.
+pub const DEFAULT_MAX_WARNINGS: usize = 64;
+
+/// The context a TIME value is evaluated in: the `ParseMode` to apply and a sink for the
+/// warnings raised on the way.
+///
+/// At most `max_warnings` warnings are kept, but every warning is counted, like MySQL's
+/// `@@warning_count` keeps counting past `max_error_count`.
+#[derive(Debug, Clone)]
+pub struct EvalContext {
+    pub mode: ParseMode,
+    max_warnings: usize,
+    warnings: Vec<Warning>,
+    counts: [usize; Warning::COUNT],
+}
+
+impl Default for EvalContext {
+    fn default() -> Self {
+        EvalContext::new(ParseMode::default())
+    }
+}
+
+impl EvalContext {
+    pub fn new(mode: ParseMode) -> Self {
+        EvalContext {
+            mode,
+            max_warnings: DEFAULT_MAX_WARNINGS,
+            warnings: Vec::new(),
+            counts: [0; Warning::COUNT],
+        }
+    }
+
+    pub fn with_max_warnings(mut self, max_warnings: usize) -> Self {
+        self.max_warnings = max_warnings;
+        self
+    }
+
+    #[inline]
+    pub fn warn(&mut self, warning: Warning) {
+        self.counts[warning.index()] += 1;
+        if self.warnings.len() < self.max_warnings {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// The recorded warnings in the order they were raised, at most `max_warnings` of them.
+    #[inline]
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// How many times `warning` was raised, including the occurrences that were not recorded.
+    #[inline]
+    pub fn count(&self, warning: Warning) -> usize {
+        self.counts[warning.index()]
+    }
+
+    /// How many warnings were raised in total, including the ones that were not recorded.
+    #[inline]
+    pub fn warning_count(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.warnings.clear();
+        self.counts = [0; Warning::COUNT];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_longest_prefix() {
+        // Accepts runs of digits only, rejecting the empty input.
+        let digits = |s: &[u8]| match s.iter().position(|c| !c.is_ascii_digit()) {
+            _ if s.is_empty() => Err(ParseError::EmptyInput),
+            Some(i) => Err(ParseError::InvalidCharacter(i)),
+            None => Ok(s.len()),
+        };
+        assert_eq!(parse_longest_prefix(b"123", digits), Ok((3, 3)));
+        assert_eq!(parse_longest_prefix(b"12ab3", digits), Ok((2, 2)));
+        assert_eq!(
+            parse_longest_prefix(b"ab", digits),
+            Err(ParseError::InvalidCharacter(0))
+        );
+        assert_eq!(
+            parse_longest_prefix(b"99x", |_| Err::<(), _>(ParseError::Overflow(1))),
+            Err(ParseError::Overflow(1))
+        );
+
+        // The empty prefix does not count, even for a parser that accepts it.
+        let lenient = |s: &[u8]| match s.iter().position(|c| !c.is_ascii_digit()) {
+            Some(i) => Err(ParseError::InvalidCharacter(i)),
+            None => Ok(s.len()),
+        };
+        assert_eq!(
+            parse_longest_prefix(b"a1", lenient),
+            Err(ParseError::InvalidCharacter(0))
+        );
+        assert_eq!(parse_longest_prefix(b"1a", lenient), Ok((1, 1)));
+        assert_eq!(parse_longest_prefix(b"", lenient), Ok((0, 0)));
+
+        // Neither does a blank one.
+        let spaced = |s: &[u8]| {
+            trim_input(s)?;
+            match s.iter().position(|&c| !c.is_ascii_digit() && c != b' ') {
+                Some(i) => Err(ParseError::InvalidCharacter(i)),
+                None => Ok(s.len()),
+            }
+        };
+        assert_eq!(parse_longest_prefix(b" 1 a", spaced), Ok((3, 3)));
+        assert_eq!(
+            parse_longest_prefix(b" a", spaced),
+            Err(ParseError::InvalidCharacter(1))
+        );
+    }
+
+    #[test]
+    fn test_warnings_cap() {
+        let mut ctx = EvalContext::default().with_max_warnings(2);
+        ctx.warn(Warning::TruncatedFraction);
+        ctx.warn(Warning::ClampedRange);
+        ctx.warn(Warning::TruncatedFraction);
+        assert_eq!(
+            ctx.warnings(),
+            &[Warning::TruncatedFraction, Warning::ClampedRange]
+        );
+        assert_eq!(ctx.count(Warning::TruncatedFraction), 2);
+        assert_eq!(ctx.warning_count(), 3);
+        ctx.clear();
+        assert_eq!(ctx.warning_count(), 0);
+        assert!(ctx.warnings().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+//! The steps of reading a TIME literal that every parser of the workspace takes the same way.
+
+use crate::{ParseError, ParseMode};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+const MAX_HOURS: u64 = 838;
+const MAX_MINUTES: u64 = 59;
+const MAX_SECONDS: u64 = 59;
+
+/// `s` without the ASCII whitespace around it, which TIME parsers skip and count as part of the
+/// value. Input that is empty or only whitespace is `EmptyInput`.
+pub fn trim_input(s: &[u8]) -> Result<&[u8]> {
+    let start = s
+        .iter()
+        .position(|c| !c.is_ascii_whitespace())
+        .ok_or(ParseError::EmptyInput)?;
+    let end = s.len()
+        - s.iter()
+            .rev()
+            .take_while(|c| c.is_ascii_whitespace())
+            .count();
+    Ok(&s[start..end])
+}
+
+/// Split `s`, a TIME literal trimmed by `trim_input`, into its day and the rest. Only digits
+/// followed by whitespace make a day; any other whitespace ends the value, and the error points
+/// at what follows it, so that `parse_longest_prefix` keeps the whitespace. Offsets are relative
+/// to `s`.
+pub fn split_day(s: &[u8]) -> Result<(Option<&[u8]>, &[u8])> {
+    let skip_whitespace = |from: usize| {
+        from + s[from..]
+            .iter()
+            .take_while(|c| c.is_ascii_whitespace())
+            .count()
+    };
+    let space = match s.iter().position(u8::is_ascii_whitespace) {
+        Some(space) => space,
+        None => return Ok((None, s)),
+    };
+    if !s[..space].iter().all(u8::is_ascii_digit) {
+        return Err(ParseError::InvalidCharacter(skip_whitespace(space)));
+    }
+    let start = skip_whitespace(space);
+    match s[start..].iter().position(u8::is_ascii_whitespace) {
+        Some(end) => Err(ParseError::InvalidCharacter(skip_whitespace(start + end))),
+        None => Ok((Some(&s[..space]), &s[start..])),
+    }
+}
+
+/// Check the hour field of a literal, which starts at `offset`. With `ParseMode::Truncate` too
+/// many hours are only capped here, so that adding the day cannot overflow, and the value gets
+/// clamped once it is built.
+#[inline]
+pub fn check_hour_with_mode(hour: u64, mode: ParseMode, offset: usize) -> Result<u64> {
+    match mode {
+        ParseMode::Truncate => Ok(hour.min(MAX_HOURS + 1)),
+        ParseMode::Strict if hour > MAX_HOURS => Err(ParseError::HourOutOfRange(offset)),
+        ParseMode::Strict => Ok(hour),
+    }
+}
+
+/// Split the compact `[h..]hhmmss` form into its hour, minute and second, `number` being the
+/// `len` digits at `offset`. The fields are checked from the left like the ones separated by
+/// colons, the minute and second being the last four digits. Once the hour is capped they no
+/// longer matter and are left at zero.
+pub fn split_hhmmss(
+    number: u64,
+    mode: ParseMode,
+    offset: usize,
+    len: usize,
+) -> Result<(u64, u64, u64)> {
+    let hour = check_hour_with_mode(number / 10000, mode, offset)?;
+    if hour > MAX_HOURS {
+        return Ok((hour, 0, 0));
+    }
+    let field = |digits: usize| offset + len.saturating_sub(digits);
+    let (minute, second) = (number / 100 % 100, number % 100);
+    if minute > MAX_MINUTES {
+        return Err(ParseError::MinuteOutOfRange(field(4)));
+    }
+    if second > MAX_SECONDS {
+        return Err(ParseError::SecondOutOfRange(field(2)));
+    }
+    Ok((hour, minute, second))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_day() {
+        let cases: Vec<(&[u8], _)> = vec![
+            (b"12:34:56", Ok((None, &b"12:34:56"[..]))),
+            (b"1 12:34", Ok((Some(&b"1"[..]), &b"12:34"[..]))),
+            (b"1 \t12", Ok((Some(&b"1"[..]), &b"12"[..]))),
+            (b"12:34:56 PM", Err(ParseError::InvalidCharacter(9))),
+            (b"12:34:56  x", Err(ParseError::InvalidCharacter(10))),
+            (b"1 12:34:56 PM", Err(ParseError::InvalidCharacter(11))),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(split_day(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_trim_input() {
+        assert_eq!(trim_input(b" \t12:34 \n"), Ok(&b"12:34"[..]));
+        assert_eq!(trim_input(b"1 2"), Ok(&b"1 2"[..]));
+        assert_eq!(trim_input(b""), Err(ParseError::EmptyInput));
+        assert_eq!(trim_input(b" \t"), Err(ParseError::EmptyInput));
+    }
+
+    #[test]
+    fn test_split_hhmmss() {
+        use ParseMode::{Strict, Truncate};
+
+        let cases = vec![
+            (123456, Strict, Ok((12, 34, 56))),
+            (56, Strict, Ok((0, 0, 56))),
+            (8385959, Strict, Ok((838, 59, 59))),
+            (8390000, Strict, Err(ParseError::HourOutOfRange(1))),
+            (8390000, Truncate, Ok((839, 0, 0))),
+            (99996000, Truncate, Ok((839, 0, 0))),
+            (126000, Strict, Err(ParseError::MinuteOutOfRange(3))),
+            (126000, Truncate, Err(ParseError::MinuteOutOfRange(3))),
+            (123460, Strict, Err(ParseError::SecondOutOfRange(5))),
+            (60, Strict, Err(ParseError::SecondOutOfRange(1))),
+        ];
+        for (number, mode, expected) in cases {
+            let len = number.to_string().len();
+            // The digits start at offset 1, after a sign.
+            assert_eq!(split_hhmmss(number, mode, 1, len), expected, "{}", number);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Methods every `Duration` of the workspace has in the same form, written once here. They are
+//! inherent methods of each type rather than a trait, so callers need no extra import.
+
+/// Implement `parse_prefix` and `parse_prefix_with_ctx` on a TIME type from its
+/// `parse_with_ctx(&mut EvalContext, &[u8], i8) -> Result<Self, ParseError>`.
+#[macro_export]
+macro_rules! impl_parse_prefix {
+    ($ty:ident) => {
+        impl $ty {
+            /// Parse the longest prefix of `s` that is a valid TIME value, returning the value
+            /// and how many bytes of `s` it took, like MySQL reads `'12:34:56abc'` as
+            /// `12:34:56`.
+            pub fn parse_prefix(
+                s: &[u8],
+                fsp: i8,
+            ) -> ::std::result::Result<($ty, usize), $crate::ParseError> {
+                $ty::parse_prefix_with_ctx(&mut $crate::EvalContext::default(), s, fsp)
+            }
+
+            /// Parse the longest valid prefix like `parse_prefix`, recording the warnings raised
+            /// on the way into `ctx`, plus `Warning::TruncatedTrailing` if anything of `s` was
+            /// left over.
+            pub fn parse_prefix_with_ctx(
+                ctx: &mut $crate::EvalContext,
+                s: &[u8],
+                fsp: i8,
+            ) -> ::std::result::Result<($ty, usize), $crate::ParseError> {
+                let (value, len) = $crate::parse_longest_prefix(s, |prefix| {
+                    $ty::parse_with_ctx(ctx, prefix, fsp)
+                })?;
+                if len < s.len() {
+                    ctx.warn($crate::Warning::TruncatedTrailing);
+                }
+                Ok((value, len))
+            }
+        }
+    };
+}
+
+/// Implement `from_nanos` and `from_micros` on a TIME type from its
+/// `from_std(std::time::Duration, bool, i8) -> Result<Self, ParseError>`, and the private
+/// `clear_neg_if_zero` from its `hour`, `minute`, `second`, `nano` and `set_neg`.
+#[macro_export]
+macro_rules! impl_signed_conversions {
+    ($ty:ident) => {
+        impl $ty {
+            /// Convert a signed number of nanoseconds, rounding the fraction to `fsp`.
+            pub fn from_nanos(
+                nanos: i64,
+                fsp: i8,
+            ) -> ::std::result::Result<$ty, $crate::ParseError> {
+                $ty::from_std(
+                    ::std::time::Duration::from_nanos(nanos.unsigned_abs()),
+                    nanos < 0,
+                    fsp,
+                )
+            }
+
+            /// Convert a signed number of microseconds, rounding the fraction to `fsp`.
+            pub fn from_micros(
+                micros: i64,
+                fsp: i8,
+            ) -> ::std::result::Result<$ty, $crate::ParseError> {
+                $ty::from_std(
+                    ::std::time::Duration::from_micros(micros.unsigned_abs()),
+                    micros < 0,
+                    fsp,
+                )
+            }
+
+            /// A zero value has no sign.
+            #[inline]
+            fn clear_neg_if_zero(mut self) -> Self {
+                if self.hour() == 0 && self.minute() == 0 && self.second() == 0 && self.nano() == 0
+                {
+                    self.set_neg(false);
+                }
+                self
+            }
+        }
+    };
+}
+
+/// Implement `Serialize` and `Deserialize` on a TIME type through `serialize_time` and
+/// `deserialize_time`: the MySQL string in human-readable formats such as JSON, the signed
+/// nanoseconds and fsp in binary formats such as bincode. Strings are parsed with the fsp of their
+/// fraction, nanoseconds are range checked.
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! impl_serde {
+    ($ty:ident) => {
+        impl $crate::serde::Serialize for $ty {
+            fn serialize<S: $crate::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::serialize_time(serializer, self.to_time_str(), self.to_nanos(), self.fsp())
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $ty {
+            fn deserialize<D: $crate::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                $crate::deserialize_time(deserializer, $ty::parse, $ty::from_nanos)
+            }
+        }
+    };
+}
+
+/// Define the `serde_round_trip` test of a type with `impl_serde!`, which runs the serde cases of
+/// `testdata` through `serde_json` and `bincode`, dev-dependencies of the calling crate.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! serde_round_trip_test {
+    ($ty:ident) => {
+        #[test]
+        fn serde_round_trip() {
+            for &(input, fsp, expected) in $crate::testdata::SERDE_ROUND_TRIPS {
+                let value = $ty::parse(input.as_bytes(), fsp).unwrap();
+                let json = serde_json::to_string(&value).unwrap();
+                assert_eq!(json, format!("\"{}\"", expected));
+                let decoded: $ty = serde_json::from_str(&json).unwrap();
+                assert_eq!(decoded.to_bits(), value.to_bits(), "{}", json);
+
+                let bytes = bincode::serialize(&value).unwrap();
+                assert_eq!(bytes.len(), 9, "{}", input);
+                let decoded: $ty = bincode::deserialize(&bytes).unwrap();
+                assert_eq!(decoded.to_bits(), value.to_bits(), "{}", input);
+            }
+
+            for json in $crate::testdata::SERDE_INVALID_JSON {
+                assert!(serde_json::from_str::<$ty>(json).is_err(), "{}", json);
+            }
+            for &(nanos, fsp) in $crate::testdata::SERDE_INVALID_BINARY {
+                let bytes = bincode::serialize(&(nanos, fsp)).unwrap();
+                assert!(bincode::deserialize::<$ty>(&bytes).is_err(), "{}", nanos);
+            }
+        }
+    };
+}
@@ -0,0 +1,214 @@
+use crate::ParseError;
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+const NANO_WIDTH: usize = 9;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// The largest `hhmmss` number that is a valid TIME, `838:59:59`.
+pub const MAX_TIME_NUMBER: u64 = 8385959;
+
+/// Numbers from here on are too long to be a `YYYYMMDDhhmmss` datetime number.
+const MAX_DATETIME_NUMBER: u64 = 100_000_000_000_000;
+
+#[inline]
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Check the date part of a DATETIME-shaped value. The zero date `0000-00-00` is allowed.
+#[inline]
+pub fn check_date(year: u64, month: u64, day: u64) -> Result<()> {
+    if year == 0 && month == 0 && day == 0 {
+        return Ok(());
+    }
+    if year > 9999 || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(ParseError::InvalidDate(0));
+    }
+    Ok(())
+}
+
+/// Resolve the `hhmmss` part of the absolute value of a number converted to TIME, following
+/// MySQL's rules: numbers up to `MAX_TIME_NUMBER` are `hhmmss` already, larger positive ones are
+/// only allowed as `YYYYMMDDhhmmss` (or `YYMMDDhhmmss`) datetime numbers, of which the time of
+/// day is kept.
+pub fn split_time_number(neg: bool, number: u64) -> Result<u64> {
+    if number <= MAX_TIME_NUMBER {
+        return Ok(number);
+    }
+    if neg || !(10_000_000_000..MAX_DATETIME_NUMBER).contains(&number) {
+        return Err(ParseError::Overflow(0));
+    }
+    let (date, time) = (number / 1_000_000, number % 1_000_000);
+    let mut year = date / 10000;
+    if number < 1_000_000_000_000 {
+        year += if year < 70 { 2000 } else { 1900 };
+    }
+    check_date(year, date / 100 % 100, date % 100)?;
+    if time / 10000 > 23 {
+        return Err(ParseError::HourOutOfRange(0));
+    }
+    Ok(time)
+}
+
+/// A decimal number split into the parts a TIME value is built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decimal {
+    pub neg: bool,
+    pub integer: u64,
+    /// The first nine digits of the fraction, the rest is dropped.
+    pub nanos: u64,
+}
+
+impl Decimal {
+    /// Split a decimal like `-123456.789` or `1.23456789e5`.
+    pub fn parse(s: &[u8]) -> Result<Decimal> {
+        let (neg, start) = match s.first() {
+            None => return Err(ParseError::EmptyInput),
+            Some(b'-') => (true, 1),
+            Some(b'+') => (false, 1),
+            Some(_) => (false, 0),
+        };
+        let end = s
+            .iter()
+            .position(|&c| c == b'e' || c == b'E')
+            .unwrap_or(s.len());
+
+        let mantissa = &s[start..end];
+        let (mut int_len, mut len) = (None, 0);
+        for (i, &c) in mantissa.iter().enumerate() {
+            if c == b'.' && int_len.is_none() {
+                int_len = Some(len);
+            } else if c.is_ascii_digit() {
+                len += 1;
+            } else {
+                return Err(ParseError::InvalidCharacter(start + i));
+            }
+        }
+        if len == 0 {
+            return Err(ParseError::InvalidCharacter(end));
+        }
+
+        let mut exp = 0i64;
+        if end < s.len() {
+            let (exp_neg, digits) = match s.get(end + 1) {
+                Some(b'-') => (true, end + 2),
+                Some(b'+') => (false, end + 2),
+                _ => (false, end + 1),
+            };
+            if digits == s.len() {
+                return Err(ParseError::InvalidCharacter(s.len()));
+            }
+            for (i, &c) in s[digits..].iter().enumerate() {
+                if !c.is_ascii_digit() {
+                    return Err(ParseError::InvalidCharacter(digits + i));
+                }
+                // Anything beyond this overflows or rounds to zero anyway.
+                exp = (exp * 10 + i64::from(c - b'0')).min(1 << 20);
+            }
+            if exp_neg {
+                exp = -exp;
+            }
+        }
+
+        // The position of the decimal point after applying the exponent.
+        let point = int_len.unwrap_or(len) as i64 + exp;
+        let (mut integer, mut nanos) = (0u64, 0u64);
+        let digits = mantissa.iter().filter(|c| c.is_ascii_digit());
+        for (k, &c) in digits.enumerate() {
+            let d = u64::from(c - b'0');
+            let pos = k as i64 - point;
+            if pos < 0 {
+                integer = integer * 10 + d;
+                if integer >= MAX_DATETIME_NUMBER {
+                    return Err(ParseError::Overflow(0));
+                }
+            } else if pos < NANO_WIDTH as i64 {
+                nanos += d * 10u64.pow(NANO_WIDTH as u32 - 1 - pos as u32);
+            }
+        }
+        if integer != 0 {
+            for _ in len as i64..point {
+                integer *= 10;
+                if integer >= MAX_DATETIME_NUMBER {
+                    return Err(ParseError::Overflow(0));
+                }
+            }
+        }
+        Ok(Decimal {
+            neg,
+            integer,
+            nanos,
+        })
+    }
+
+    /// Split a float. The fraction is rounded to nanoseconds.
+    pub fn from_f64(value: f64) -> Result<Decimal> {
+        let abs = value.abs();
+        if !abs.is_finite() || abs >= MAX_DATETIME_NUMBER as f64 {
+            return Err(ParseError::Overflow(0));
+        }
+        let mut integer = abs.trunc() as u64;
+        let mut nanos = ((abs - abs.trunc()) * NANOS_PER_SEC as f64).round() as u64;
+        if nanos == NANOS_PER_SEC {
+            integer += 1;
+            nanos = 0;
+        }
+        Ok(Decimal {
+            neg: value < 0.0,
+            integer,
+            nanos,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_parse() {
+        type Parts = (bool, u64, u64);
+        let cases: Vec<(&str, Result<Parts>)> = vec![
+            ("123456.789", Ok((false, 123456, 789_000_000))),
+            ("-1.5", Ok((true, 1, 500_000_000))),
+            ("+.25", Ok((false, 0, 250_000_000))),
+            ("1.23456789e5", Ok((false, 123456, 789_000_000))),
+            ("123456789E-3", Ok((false, 123456, 789_000_000))),
+            ("5e-10", Ok((false, 0, 0))),
+            ("0.1234567891", Ok((false, 0, 123_456_789))),
+            ("1e14", Err(ParseError::Overflow(0))),
+            ("", Err(ParseError::EmptyInput)),
+            ("-", Err(ParseError::InvalidCharacter(1))),
+            ("1.2.3", Err(ParseError::InvalidCharacter(3))),
+            ("1e", Err(ParseError::InvalidCharacter(2))),
+            ("1e+x", Err(ParseError::InvalidCharacter(3))),
+        ];
+        for (s, expected) in cases {
+            let actual = Decimal::parse(s.as_bytes()).map(|d| (d.neg, d.integer, d.nanos));
+            assert_eq!(actual, expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_split_time_number() {
+        assert_eq!(split_time_number(true, 8385959), Ok(8385959));
+        assert_eq!(split_time_number(false, 20190515123456), Ok(123456));
+        assert_eq!(split_time_number(false, 190515123456), Ok(123456));
+        assert_eq!(
+            split_time_number(true, 20190515123456),
+            Err(ParseError::Overflow(0))
+        );
+        assert_eq!(
+            split_time_number(false, 20190229123456),
+            Err(ParseError::InvalidDate(0))
+        );
+    }
+}
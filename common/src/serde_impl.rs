@@ -0,0 +1,119 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeTuple, Serializer};
+
+use crate::{ParseError, TimeStr};
+
+const MAX_FSP: usize = 6;
+
+/// The number of fraction digits of a TIME literal, which is the fsp it was printed with.
+fn fraction_digits(s: &[u8]) -> i8 {
+    match s.iter().position(|&c| c == b'.') {
+        Some(dot) => s[dot + 1..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count()
+            .min(MAX_FSP) as i8,
+        None => 0,
+    }
+}
+
+/// Serialize a TIME value as its MySQL string in human-readable formats, and as a tuple of its
+/// signed nanoseconds and fsp in the others.
+pub fn serialize_time<S: Serializer>(
+    serializer: S,
+    time: TimeStr,
+    nanos: i64,
+    fsp: u8,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(time.as_str())
+    } else {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&nanos)?;
+        tuple.serialize_element(&fsp)?;
+        tuple.end()
+    }
+}
+
+/// Deserialize what `serialize_time` wrote. The string form goes through `parse` with the fsp
+/// of its fraction, the other through `from_nanos`, so every value is validated.
+pub fn deserialize_time<'de, D, T, P, N>(
+    deserializer: D,
+    parse: P,
+    from_nanos: N,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    P: FnOnce(&[u8], i8) -> Result<T, ParseError>,
+    N: FnOnce(i64, i8) -> Result<T, ParseError>,
+{
+    let visitor = TimeVisitor {
+        parse,
+        from_nanos,
+        value: PhantomData,
+    };
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_tuple(2, visitor)
+    }
+}
+
+struct TimeVisitor<T, P, N> {
+    parse: P,
+    from_nanos: N,
+    value: PhantomData<T>,
+}
+
+impl<'de, T, P, N> Visitor<'de> for TimeVisitor<T, P, N>
+where
+    P: FnOnce(&[u8], i8) -> Result<T, ParseError>,
+    N: FnOnce(i64, i8) -> Result<T, ParseError>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TIME string or a tuple of nanoseconds and fsp")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+        (self.parse)(s.as_bytes(), fraction_digits(s.as_bytes())).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let nanos: i64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let fsp: u8 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let fsp = i8::try_from(fsp).map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Unsigned(fsp.into()), &"an fsp from 0 to 6")
+        })?;
+        (self.from_nanos)(nanos, fsp).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fraction_digits() {
+        let cases = vec![
+            ("12:34:56", 0),
+            ("12:34:56.5", 1),
+            ("-00:00:00.000", 3),
+            ("1 2:3:4.123456", 6),
+            ("12:34:56.1234567", 6),
+            ("12:34:56.", 0),
+        ];
+        for (input, fsp) in cases {
+            assert_eq!(fraction_digits(input.as_bytes()), fsp, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Inputs every TIME parser of the workspace must treat the same way, so that their tests check
+//! one set of expectations instead of each keeping its own.
+//!
+//! Errors are reported at the start of the offending field; when several fields are wrong, the
+//! leftmost one wins. A value out of the TIME range as a whole is reported at its most significant
+//! field, the day if there is one and the hour otherwise. In the compact `[h..]hhmmss` form the
+//! second is the last two digits, the minute the two before and the hour whatever is left.
+//!
+//! ASCII whitespace around the value is skipped and counted as part of it, and input that is
+//! empty or only whitespace is `EmptyInput`.
+
+use crate::ParseError;
+use crate::ParseMode::{self, Strict, Truncate};
+use crate::Warning::{self, ClampedRange, TruncatedFraction, TruncatedTrailing};
+
+/// Inputs `Duration::parse` rejects, with their fsp and the expected error.
+pub const PARSE_ERRORS: &[(&str, i8, ParseError)] = &[
+    ("12:34:56", 7, ParseError::InvalidFsp(7)),
+    ("", 6, ParseError::EmptyInput),
+    (" \t ", 6, ParseError::EmptyInput),
+    ("abc", 6, ParseError::InvalidCharacter(0)),
+    ("12:3x:00", 6, ParseError::InvalidCharacter(4)),
+    ("12:34:5x", 6, ParseError::InvalidCharacter(7)),
+    ("12:34:56.5x", 6, ParseError::InvalidCharacter(10)),
+    ("12:34:", 6, ParseError::DanglingColon(5)),
+    ("12:61:00", 6, ParseError::MinuteOutOfRange(3)),
+    ("1 12:61:00", 6, ParseError::MinuteOutOfRange(5)),
+    ("12:34:60", 6, ParseError::SecondOutOfRange(6)),
+    ("839:00:00", 6, ParseError::HourOutOfRange(0)),
+    ("-839:00:00", 6, ParseError::HourOutOfRange(1)),
+    ("35 00:00:00", 6, ParseError::HourOutOfRange(0)),
+    ("838:59:59.5", 0, ParseError::HourOutOfRange(0)),
+    ("838:59:59.5", 1, ParseError::HourOutOfRange(0)),
+    ("838:59:59.000001", 6, ParseError::HourOutOfRange(0)),
+    ("9999999", 6, ParseError::HourOutOfRange(0)),
+    (
+        "99999999999999999999:00:00",
+        6,
+        ParseError::HourOutOfRange(0),
+    ),
+    (
+        "99999999999999999999 00:00:00",
+        6,
+        ParseError::HourOutOfRange(0),
+    ),
+    ("126000", 6, ParseError::MinuteOutOfRange(2)),
+    ("123460", 6, ParseError::SecondOutOfRange(4)),
+];
+
+/// Inputs `Duration::parse_prefix_with_ctx` accepts, with their fsp, the mode of the context, the
+/// number of bytes the value takes and the warnings expected in the context.
+pub const PARSE_WARNINGS: &[(&str, i8, ParseMode, usize, &[Warning])] = &[
+    ("12:34:56.1200", 2, Strict, 13, &[]),
+    ("12:34:56.1234567", 6, Strict, 16, &[TruncatedFraction]),
+    ("12:34:56.125", 2, Strict, 12, &[TruncatedFraction]),
+    ("839:00:00", 0, Truncate, 9, &[ClampedRange]),
+    ("-1000:00:00", 0, Truncate, 11, &[ClampedRange]),
+    ("35 00:00:00", 0, Truncate, 11, &[ClampedRange]),
+    ("9999999", 0, Truncate, 7, &[ClampedRange]),
+    ("99999999999999999999", 0, Truncate, 20, &[ClampedRange]),
+    (
+        "99999999999999999999:00:00",
+        0,
+        Truncate,
+        26,
+        &[ClampedRange],
+    ),
+    (
+        "99999999999999999999 00:00:00",
+        0,
+        Truncate,
+        29,
+        &[ClampedRange],
+    ),
+    ("838:59:59.4", 0, Truncate, 11, &[TruncatedFraction]),
+    ("838:59:59.5", 1, Truncate, 11, &[ClampedRange]),
+    (
+        "838:59:59.5",
+        0,
+        Truncate,
+        11,
+        &[TruncatedFraction, ClampedRange],
+    ),
+    ("12:34:56abc", 0, Strict, 8, &[TruncatedTrailing]),
+    ("12:34:56 PM", 0, Strict, 9, &[TruncatedTrailing]),
+    ("12:34:56 ", 0, Strict, 9, &[]),
+    ("1 12:34:56 PM", 0, Strict, 11, &[TruncatedTrailing]),
+    (
+        "12:34:56.789xyz",
+        1,
+        Strict,
+        12,
+        &[TruncatedFraction, TruncatedTrailing],
+    ),
+    (
+        "839:00:00x",
+        0,
+        Truncate,
+        9,
+        &[ClampedRange, TruncatedTrailing],
+    ),
+    ("123456 x", 0, Strict, 7, &[TruncatedTrailing]),
+    (" 12:34:56\t", 0, Strict, 10, &[]),
+];
+
+/// Values of `impl_serde!`, with their fsp and the string of their human-readable form. The
+/// binary form is the signed nanoseconds and the fsp, 9 bytes in bincode.
+pub const SERDE_ROUND_TRIPS: &[(&str, i8, &str)] = &[
+    ("12:34:56", 0, "12:34:56"),
+    ("-1:2:3.5", 1, "-01:02:03.5"),
+    ("-838:59:59", 6, "-838:59:59.000000"),
+    ("-0:0:0.25", 2, "-00:00:00.25"),
+    ("0", 3, "00:00:00.000"),
+];
+
+/// JSON that `impl_serde!` rejects.
+pub const SERDE_INVALID_JSON: &[&str] = &["\"839:00:00\"", "\"12:34:abc\"", "123456", "[0, 0]"];
+
+/// Nanoseconds and fsp of the binary form that `impl_serde!` rejects.
+pub const SERDE_INVALID_BINARY: &[(i64, u8)] = &[
+    (839 * 3600 * 1_000_000_000, 0),
+    (-839 * 3600 * 1_000_000_000, 6),
+    (0, 7),
+    (0, 200),
+];
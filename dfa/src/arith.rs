@@ -0,0 +1,156 @@
+use crate::{
+    Duration, DurationBuilder, ParseMode, MAX_HOURS, MAX_MINUTES, MAX_SECONDS, MINUTES_PER_HOUR,
+    NANOS_PER_SEC, NANO_WIDTH, SECS_PER_MINUTE,
+};
+
+/// `838:59:59` in nanoseconds.
+pub(crate) const MAX_NANOS: u128 = ((MAX_HOURS * MINUTES_PER_HOUR + MAX_MINUTES) * SECS_PER_MINUTE
+    + MAX_SECONDS) as u128
+    * NANOS_PER_SEC as u128;
+
+impl Duration {
+    /// Build the result of an operation from its signed nanoseconds, rounded to `fsp`. Past the
+    /// TIME range it fails with `ParseMode::Strict` and is clamped with `ParseMode::Truncate`,
+    /// which never fails.
+    pub(crate) fn from_total_nanos(nanos: i128, fsp: u8, mode: ParseMode) -> Option<Duration> {
+        let mut abs = nanos.unsigned_abs();
+        if abs > MAX_NANOS {
+            if mode == ParseMode::Strict {
+                return None;
+            }
+            abs = MAX_NANOS;
+        }
+        let (secs, nano) = (
+            (abs / NANOS_PER_SEC as u128) as u64,
+            (abs % NANOS_PER_SEC as u128) as u64,
+        );
+        Duration::build(DurationBuilder {
+            neg: nanos < 0,
+            hour: secs / (SECS_PER_MINUTE * MINUTES_PER_HOUR),
+            minute: secs / SECS_PER_MINUTE % MINUTES_PER_HOUR,
+            second: secs % SECS_PER_MINUTE,
+            nano,
+            fsp,
+            round_with_fsp: true,
+            mode,
+        })
+        .ok()
+        .map(|(duration, _)| duration.clear_neg_if_zero())
+    }
+
+    fn add_with_mode(self, rhs: Duration, mode: ParseMode) -> Option<Duration> {
+        let nanos = i128::from(self.to_nanos()) + i128::from(rhs.to_nanos());
+        Duration::from_total_nanos(nanos, self.fsp().max(rhs.fsp()), mode)
+    }
+
+    fn mul_with_mode(self, rhs: i64, mode: ParseMode) -> Option<Duration> {
+        let nanos = i128::from(self.to_nanos()) * i128::from(rhs);
+        Duration::from_total_nanos(nanos, self.fsp(), mode)
+    }
+
+    /// `self + rhs` with the larger fsp of both, `None` past `±838:59:59`, like `ADDTIME`.
+    pub fn checked_add(self, rhs: Duration) -> Option<Duration> {
+        self.add_with_mode(rhs, ParseMode::Strict)
+    }
+
+    /// `self - rhs` with the larger fsp of both, `None` past `±838:59:59`, like `SUBTIME`.
+    pub fn checked_sub(self, rhs: Duration) -> Option<Duration> {
+        self.add_with_mode(rhs.checked_neg()?, ParseMode::Strict)
+    }
+
+    /// `-self`, which is always in range.
+    pub fn checked_neg(self) -> Option<Duration> {
+        Duration::from_total_nanos(-i128::from(self.to_nanos()), self.fsp(), ParseMode::Strict)
+    }
+
+    /// `self * rhs`, `None` past `±838:59:59`.
+    pub fn checked_mul(self, rhs: i64) -> Option<Duration> {
+        self.mul_with_mode(rhs, ParseMode::Strict)
+    }
+
+    /// `self / rhs` rounded to the fsp of `self`, `None` if `rhs` is zero.
+    pub fn checked_div(self, rhs: i64) -> Option<Duration> {
+        if rhs == 0 {
+            return None;
+        }
+        let nanos = i128::from(self.to_nanos());
+        // Round half away from zero once, straight to the last digit of the fsp.
+        let unit = 10i128.pow(NANO_WIDTH - u32::from(self.fsp()));
+        let divisor = i128::from(rhs) * unit;
+        let quotient = (2 * nanos + nanos.signum() * divisor.abs()) / (2 * divisor);
+        Duration::from_total_nanos(quotient * unit, self.fsp(), ParseMode::Strict)
+    }
+
+    /// `self + rhs` clamped to `±838:59:59`, like `ADDTIME` in non-strict mode.
+    pub fn saturating_add(self, rhs: Duration) -> Duration {
+        self.add_with_mode(rhs, ParseMode::Truncate).unwrap()
+    }
+
+    /// `self - rhs` clamped to `±838:59:59`, like `SUBTIME` in non-strict mode.
+    pub fn saturating_sub(self, rhs: Duration) -> Duration {
+        self.add_with_mode(rhs.checked_neg().unwrap(), ParseMode::Truncate)
+            .unwrap()
+    }
+
+    /// `self * rhs` clamped to `±838:59:59`.
+    pub fn saturating_mul(self, rhs: i64) -> Duration {
+        self.mul_with_mode(rhs, ParseMode::Truncate).unwrap()
+    }
+}
+
+#[test]
+fn arithmetic() {
+    use crate::d;
+
+    let max = d("838:59:59", 0);
+    let min = d("-838:59:59", 0);
+
+    let sum = d("12:59:59.5", 1).checked_add(d("0:0:0.75", 2)).unwrap();
+    assert_eq!(sum.to_string(), "13:00:00.25");
+    let diff = d("1:00:00", 0).checked_sub(d("1:00:00.000001", 6)).unwrap();
+    assert_eq!(diff.to_string(), "-00:00:00.000001");
+    assert_eq!(
+        d("-1:2:3", 0).checked_neg().unwrap().to_string(),
+        "01:02:03"
+    );
+    assert_eq!(d("0", 0).checked_neg().unwrap().to_string(), "00:00:00");
+    assert_eq!(
+        d("100:30:00", 0).checked_mul(-8).unwrap().to_string(),
+        "-804:00:00"
+    );
+    assert_eq!(
+        d("1:00:00.5", 1).checked_div(3).unwrap().to_string(),
+        "00:20:00.2"
+    );
+    assert_eq!(
+        d("-0:0:1", 2).checked_div(3).unwrap().to_string(),
+        "-00:00:00.33"
+    );
+    assert_eq!(
+        d("-0:0:2", 0).checked_div(-3).unwrap().to_string(),
+        "00:00:01"
+    );
+    assert_eq!(
+        d("0:0:0.004997", 6).checked_div(10000).unwrap().to_string(),
+        "00:00:00.000000"
+    );
+    assert_eq!(
+        d("-0:0:0.005", 6).checked_div(10000).unwrap().to_string(),
+        "-00:00:00.000001"
+    );
+
+    assert_eq!(max.checked_add(d("0:0:0.1", 1)), None);
+    assert_eq!(min.checked_sub(d("0:0:1", 0)), None);
+    assert_eq!(max.checked_mul(2), None);
+    assert_eq!(max.checked_mul(i64::MIN), None);
+    assert_eq!(max.checked_div(0), None);
+    assert_eq!(max.checked_sub(max), Some(d("0", 0)));
+
+    assert_eq!(max.saturating_add(d("1:00:00", 0)), max);
+    assert_eq!(min.saturating_sub(d("1:00:00", 0)), min);
+    assert_eq!(max.saturating_mul(i64::MIN), min);
+    assert_eq!(
+        d("1:00:00", 0).saturating_add(d("1:00:00", 0)),
+        d("2:00:00", 0)
+    );
+}
@@ -0,0 +1,171 @@
+use crate::{check_fsp, simd, Duration, DurationParser, EvalContext, Result};
+
+/// A column of strings laid out like a TiDB chunk column: value `i` is
+/// `data[offsets[i]..offsets[i + 1]]`, so there is one more offset than there are values.
+#[derive(Clone, Copy, Debug)]
+pub struct StrColumn<'a> {
+    pub offsets: &'a [usize],
+    pub data: &'a [u8],
+}
+
+impl<'a> StrColumn<'a> {
+    pub fn new(offsets: &'a [usize], data: &'a [u8]) -> Self {
+        StrColumn { offsets, data }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> &'a [u8] {
+        &self.data[self.offsets[i]..self.offsets[i + 1]]
+    }
+}
+
+/// Whether value `i` is set in a bitmap laid out like a chunk's null bitmap, where bit `i % 8` of
+/// byte `i / 8` is set for a valid value.
+#[inline]
+pub fn is_valid(bitmap: &[u8], i: usize) -> bool {
+    bitmap[i / 8] & (1 << (i % 8)) != 0
+}
+
+impl Duration {
+    /// Parse every value of `column`, appending the results to `out` and their validity to
+    /// `bitmap`. A value that fails to parse is appended as `Duration::zero()` with its bit
+    /// cleared, so `out` always grows by `column.len()`. `bitmap` is grown to cover the new
+    /// values if it is shorter, bits past them are left alone.
+    ///
+    /// Only an invalid `fsp` fails the whole batch, in which case nothing is appended.
+    pub fn parse_column(
+        column: StrColumn,
+        fsp: i8,
+        out: &mut Vec<Duration>,
+        bitmap: &mut Vec<u8>,
+    ) -> Result<()> {
+        Duration::parse_column_with_ctx(&mut EvalContext::default(), column, fsp, out, bitmap)
+    }
+
+    /// Parse a column like `parse_column` in the `ParseMode` of `ctx`, recording the warnings
+    /// raised by the valid values into it.
+    pub fn parse_column_with_ctx(
+        ctx: &mut EvalContext,
+        column: StrColumn,
+        fsp: i8,
+        out: &mut Vec<Duration>,
+        bitmap: &mut Vec<u8>,
+    ) -> Result<()> {
+        let parser = DurationParser::with_mode(fsp, ctx.mode)?;
+        let canonical_fsp = check_fsp(fsp)?;
+        // The bitmap is appended to bit by bit, starting wherever the previous batch stopped.
+        let start = out.len();
+        out.reserve(column.len());
+        let bitmap_len = (start + column.len()).div_ceil(8);
+        if bitmap.len() < bitmap_len {
+            bitmap.resize(bitmap_len, 0);
+        }
+        for i in 0..column.len() {
+            let s = column.get(i);
+            let value = match simd::parse_canonical(s, canonical_fsp) {
+                Some(duration) => Ok(duration),
+                None => {
+                    let mut parser = parser.clone();
+                    parser.feed(s).and_then(|_| parser.finish_with_ctx(ctx))
+                }
+            };
+            let row = start + i;
+            match value {
+                Ok(duration) => {
+                    out.push(duration);
+                    bitmap[row / 8] |= 1 << (row % 8);
+                }
+                Err(_) => {
+                    out.push(Duration::zero());
+                    bitmap[row / 8] &= !(1 << (row % 8));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_column() {
+    let values = [
+        "12:34:56",
+        "abc",
+        "",
+        "-1 2:3:4.5",
+        "839:00:00",
+        "2019-05-15 01:02:03",
+    ];
+    let mut data = Vec::new();
+    let mut offsets = vec![0];
+    for value in &values {
+        data.extend_from_slice(value.as_bytes());
+        offsets.push(data.len());
+    }
+    let column = StrColumn::new(&offsets, &data);
+
+    let (mut out, mut bitmap) = (Vec::new(), Vec::new());
+    Duration::parse_column(column, 1, &mut out, &mut bitmap).unwrap();
+    // A second batch continues the bitmap in the middle of a byte.
+    Duration::parse_column(column, 1, &mut out, &mut bitmap).unwrap();
+    assert_eq!(out.len(), values.len() * 2);
+    assert_eq!(bitmap.len(), 2);
+    for (i, duration) in out.iter().enumerate() {
+        let value = values[i % values.len()];
+        match Duration::parse(value.as_bytes(), 1) {
+            Ok(expected) => {
+                assert!(is_valid(&bitmap, i), "{}", value);
+                assert_eq!(duration.0, expected.0, "{}", value);
+            }
+            Err(_) => {
+                assert!(!is_valid(&bitmap, i), "{}", value);
+                assert_eq!(duration.0, 0, "{}", value);
+            }
+        }
+    }
+
+    assert!(Duration::parse_column(column, 7, &mut out, &mut bitmap).is_err());
+    assert_eq!(out.len(), values.len() * 2);
+
+    // A bitmap allocated ahead for more rows keeps its length and the bits past the batch.
+    let (mut out, mut bitmap) = (Vec::new(), vec![0xff; 4]);
+    Duration::parse_column(column, 1, &mut out, &mut bitmap).unwrap();
+    assert_eq!(bitmap, [0b1110_1001, 0xff, 0xff, 0xff]);
+}
+
+#[bench]
+fn bench_parse_column(b: &mut test::Bencher) {
+    let values = [
+        "12:34:56.1234",
+        "12:34:56.789",
+        "10:20:30.189",
+        "2 27:54:32.828",
+        "2 33:44:55.666777",
+        "112233.445566",
+        "1 23",
+        "1 23:12.1234567",
+    ];
+    let mut data = Vec::new();
+    let mut offsets = vec![0];
+    for value in values.iter().cycle().take(1024) {
+        data.extend_from_slice(value.as_bytes());
+        offsets.push(data.len());
+    }
+    let column = StrColumn::new(&offsets, &data);
+    let (mut out, mut bitmap) = (Vec::with_capacity(1024), Vec::with_capacity(128));
+    b.iter(|| {
+        out.clear();
+        bitmap.clear();
+        Duration::parse_column(test::black_box(column), 6, &mut out, &mut bitmap).unwrap();
+        test::black_box(&out);
+    })
+}
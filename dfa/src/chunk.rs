@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+use std::convert::TryInto;
+
+use crate::arith::MAX_NANOS;
+use crate::{check_fsp, is_valid, DatumError, Duration};
+
+const ELEMENT_LEN: usize = 8;
+
+/// A TIME column laid out like a TiDB chunk column: a null bitmap where bit `i % 8` of byte
+/// `i / 8` is set for a non-null value, and the values as fixed 8-byte little-endian `i64`
+/// nanoseconds, null ones included.
+///
+/// A column built from a chunk buffer borrows it, and is only copied on the first `push`. The
+/// fsp comes from the column type, every value is rounded to it when read.
+#[derive(Clone, Debug)]
+pub struct DurationColumn<'a> {
+    len: usize,
+    fsp: i8,
+    null_bitmap: Cow<'a, [u8]>,
+    data: Cow<'a, [u8]>,
+}
+
+impl DurationColumn<'static> {
+    /// An empty column to be appended to.
+    pub fn new(fsp: i8) -> Result<Self, DatumError> {
+        check_fsp(fsp).map_err(|_| DatumError::InvalidFsp(fsp))?;
+        Ok(DurationColumn {
+            len: 0,
+            fsp,
+            null_bitmap: Cow::Owned(Vec::new()),
+            data: Cow::Owned(Vec::new()),
+        })
+    }
+}
+
+impl<'a> DurationColumn<'a> {
+    /// View the `len` values of a chunk column without copying. Both buffers must have exactly
+    /// the length `len` values take, and every non-null value must be within `±838:59:59`.
+    pub fn from_chunk(
+        len: usize,
+        null_bitmap: &'a [u8],
+        data: &'a [u8],
+        fsp: i8,
+    ) -> Result<Self, DatumError> {
+        check_fsp(fsp).map_err(|_| DatumError::InvalidFsp(fsp))?;
+        if null_bitmap.len() != len.div_ceil(8) {
+            return Err(DatumError::InvalidLength(null_bitmap.len()));
+        }
+        if data.len() != len * ELEMENT_LEN {
+            return Err(DatumError::InvalidLength(data.len()));
+        }
+        let column = DurationColumn {
+            len,
+            fsp,
+            null_bitmap: Cow::Borrowed(null_bitmap),
+            data: Cow::Borrowed(data),
+        };
+        for i in 0..len {
+            let nanos = column.nanos(i);
+            if is_valid(null_bitmap, i) && u128::from(nanos.unsigned_abs()) > MAX_NANOS {
+                return Err(DatumError::OutOfRange(nanos));
+            }
+        }
+        Ok(column)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn fsp(&self) -> i8 {
+        self.fsp
+    }
+
+    /// The null bitmap to write into a response chunk.
+    #[inline]
+    pub fn null_bitmap(&self) -> &[u8] {
+        &self.null_bitmap
+    }
+
+    /// The values to write into a response chunk.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    #[inline]
+    fn nanos(&self, i: usize) -> i64 {
+        let element = &self.data[i * ELEMENT_LEN..(i + 1) * ELEMENT_LEN];
+        i64::from_le_bytes(element.try_into().unwrap())
+    }
+
+    /// Value `i` rounded to the fsp of the column, `None` if it is null.
+    pub fn get(&self, i: usize) -> Option<Duration> {
+        if !is_valid(&self.null_bitmap, i) {
+            return None;
+        }
+        // Values are checked to be in range, and rounding them cannot leave it.
+        Some(Duration::from_nanos(self.nanos(i), self.fsp).unwrap())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Option<Duration>> + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// Append a value, or a null one. A borrowed column is copied first.
+    pub fn push(&mut self, value: Option<Duration>) {
+        let bitmap = self.null_bitmap.to_mut();
+        if self.len.is_multiple_of(8) {
+            bitmap.push(0);
+        }
+        let nanos = match value {
+            Some(duration) => {
+                bitmap[self.len / 8] |= 1 << (self.len % 8);
+                duration.to_nanos()
+            }
+            None => 0,
+        };
+        self.data.to_mut().extend_from_slice(&nanos.to_le_bytes());
+        self.len += 1;
+    }
+}
+
+#[test]
+fn duration_column() {
+    use crate::d;
+
+    let values = vec![
+        Some(d("12:34:56.5", 1)),
+        None,
+        Some(d("-838:59:59", 0)),
+        Some(d("0", 0)),
+        None,
+        Some(d("1:02:03.456", 3)),
+        Some(d("838:59:59", 0)),
+        None,
+        Some(d("-0:0:0.25", 2)),
+    ];
+
+    let mut column = DurationColumn::new(2).unwrap();
+    for value in &values {
+        column.push(*value);
+    }
+    assert_eq!(column.len(), values.len());
+    assert_eq!(column.null_bitmap(), &[0b0110_1101, 0b1]);
+    assert_eq!(column.data().len(), values.len() * ELEMENT_LEN);
+    assert_eq!(&column.data()[8..16], &[0; 8]);
+
+    let decoded =
+        DurationColumn::from_chunk(values.len(), column.null_bitmap(), column.data(), 2).unwrap();
+    assert!(matches!(decoded.data, Cow::Borrowed(_)));
+    let strings: Vec<_> = decoded
+        .iter()
+        .map(|value| value.map(|d| d.to_string()))
+        .collect();
+    let expected = vec![
+        Some("12:34:56.50"),
+        None,
+        Some("-838:59:59.00"),
+        Some("00:00:00.00"),
+        None,
+        Some("01:02:03.46"),
+        Some("838:59:59.00"),
+        None,
+        Some("-00:00:00.25"),
+    ];
+    assert_eq!(
+        strings,
+        expected
+            .iter()
+            .map(|s| s.map(String::from))
+            .collect::<Vec<_>>()
+    );
+
+    // Appending to a borrowed column copies it and leaves the chunk buffer alone.
+    let mut appended = decoded.clone();
+    appended.push(Some(d("1:00:00", 0)));
+    assert_eq!(appended.len(), values.len() + 1);
+    assert_eq!(appended.get(values.len()), Some(d("1:00:00", 0)));
+    assert_eq!(decoded.len(), values.len());
+
+    let out_of_range = (839 * 3600 * 1_000_000_000i64).to_le_bytes();
+    assert_eq!(
+        DurationColumn::from_chunk(1, &[1], &out_of_range, 0).unwrap_err(),
+        DatumError::OutOfRange(839 * 3600 * 1_000_000_000)
+    );
+    // Null values are not checked.
+    assert!(DurationColumn::from_chunk(1, &[0], &out_of_range, 0).is_ok());
+    assert_eq!(
+        DurationColumn::from_chunk(2, &[3], &out_of_range, 0).unwrap_err(),
+        DatumError::InvalidLength(8)
+    );
+    assert_eq!(
+        DurationColumn::from_chunk(1, &[], &out_of_range, 0).unwrap_err(),
+        DatumError::InvalidLength(0)
+    );
+    assert_eq!(
+        DurationColumn::new(7).unwrap_err(),
+        DatumError::InvalidFsp(7)
+    );
+}
@@ -0,0 +1,86 @@
+use std::convert::TryInto;
+
+use crate::{DatumError, Duration, MAX_FSP};
+
+/// The sign bit of the nanoseconds, flipped in encoded keys.
+pub(crate) const SIGN_MASK: u64 = 1 << 63;
+/// The length of an encoded key.
+pub const COMPARABLE_LEN: usize = 8;
+
+impl Duration {
+    /// Append the memcomparable key of the value to `buf`, the way TiKV encodes TIME in index
+    /// keys: the signed nanoseconds as a big-endian `i64` with the sign bit flipped, so that the
+    /// bytes compare like the values. The fsp is not part of the key.
+    pub fn encode_comparable(&self, buf: &mut Vec<u8>) {
+        let key = self.to_nanos() as u64 ^ SIGN_MASK;
+        buf.extend_from_slice(&key.to_be_bytes());
+    }
+
+    /// Decode a key written by `encode_comparable` from the start of `data`, returning it with
+    /// `MAX_FSP` and the number of bytes read.
+    pub fn decode_comparable(data: &[u8]) -> Result<(Duration, usize), DatumError> {
+        let key = match data.get(..COMPARABLE_LEN) {
+            Some(key) => u64::from_be_bytes(key.try_into().unwrap()),
+            None => return Err(DatumError::Truncated(data.len())),
+        };
+        let duration = Duration::from_stored_nanos((key ^ SIGN_MASK) as i64, MAX_FSP)?;
+        Ok((duration, COMPARABLE_LEN))
+    }
+}
+
+#[test]
+fn comparable() {
+    use crate::{d, encoded};
+
+    let encode = |d: &Duration| encoded(Duration::encode_comparable, d);
+
+    let mut values = vec![
+        d("-838:59:59", 0),
+        d("-12:00:00.5", 1),
+        d("-12:00:00", 0),
+        d("-0:0:0.000001", 6),
+        d("0", 0),
+        d("0:0:0.000001", 6),
+        d("0:0:1", 2),
+        d("0:0:1.25", 2),
+        d("100:00:00", 0),
+        d("838:59:59", 6),
+    ];
+    for (i, value) in values.iter().enumerate() {
+        let buf = encode(value);
+        assert_eq!(buf.len(), COMPARABLE_LEN);
+        let (decoded, read) = Duration::decode_comparable(&buf).unwrap();
+        assert_eq!((decoded, read), (*value, COMPARABLE_LEN), "{}", value);
+        for other in &values[i..] {
+            assert_eq!(
+                buf.cmp(&encode(other)),
+                value.cmp(other),
+                "{} {}",
+                value,
+                other
+            );
+        }
+    }
+
+    // Sorting the keys sorts the values, whatever their fsp.
+    values.reverse();
+    let mut keys: Vec<_> = values.iter().map(encode).collect();
+    keys.sort();
+    values.sort();
+    for (key, value) in keys.iter().zip(&values) {
+        assert_eq!(Duration::decode_comparable(key).unwrap().0, *value);
+    }
+
+    let mut buf = encode(&d("1:00:00", 0));
+    buf.extend_from_slice(b"rest");
+    assert_eq!(Duration::decode_comparable(&buf).unwrap().1, COMPARABLE_LEN);
+    assert_eq!(
+        Duration::decode_comparable(&buf[..5]).unwrap_err(),
+        DatumError::Truncated(5)
+    );
+    let too_large = 839 * 3600 * 1_000_000_000i64;
+    assert_eq!(
+        Duration::decode_comparable(&(too_large as u64 ^ SIGN_MASK).to_be_bytes()).unwrap_err(),
+        DatumError::OutOfRange(too_large)
+    );
+}
@@ -0,0 +1,368 @@
+use bitfield::bitfield;
+use std::fmt;
+
+use duration_common::check_date;
+
+use crate::{check_fsp, Duration, EvalContext, ParseError, ParseMode, Result, Warning};
+
+const MICROS_PER_SEC: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SEC;
+/// `838:59:59` in microseconds.
+const MAX_TIME_MICROS: i64 = (838 * 3600 + 59 * 60 + 59) * MICROS_PER_SEC;
+
+bitfield! {
+    /// A DATETIME value, from `0001-01-01 00:00:00` to `9999-12-31 23:59:59.999999`, or the zero
+    /// date `0000-00-00 00:00:00`.
+    #[derive(Clone, Copy)]
+    pub struct DateTime(u64);
+    impl Debug;
+    #[inline]
+    pub u64, year, _: 63, 50;
+    #[inline]
+    u64, _, set_year: 63, 50;
+    #[inline]
+    pub u64, month, _: 49, 46;
+    #[inline]
+    u64, _, set_month: 49, 46;
+    #[inline]
+    pub u64, day, _: 45, 41;
+    #[inline]
+    u64, _, set_day: 45, 41;
+    #[inline]
+    pub u64, hour, _: 40, 36;
+    #[inline]
+    u64, _, set_hour: 40, 36;
+    #[inline]
+    pub u64, minute, _: 35, 30;
+    #[inline]
+    u64, _, set_minute: 35, 30;
+    #[inline]
+    pub u64, second, _: 29, 24;
+    #[inline]
+    u64, _, set_second: 29, 24;
+    /// The fraction in microseconds.
+    #[inline]
+    pub u64, micro, _: 23, 4;
+    #[inline]
+    u64, _, set_micro: 23, 4;
+    #[inline]
+    pub u8, fsp, _: 3, 0;
+    #[inline]
+    u8, _, set_fsp: 3, 0;
+}
+
+/// The number of days from `1970-01-01` to a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = ((153 * ((month + 9) % 12) + 2) / 5 + day - 1) as i64;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl DateTime {
+    #[allow(clippy::too_many_arguments)]
+    fn from_fields(
+        year: u64,
+        month: u64,
+        day: u64,
+        hour: u64,
+        minute: u64,
+        second: u64,
+        micro: u64,
+        fsp: u8,
+    ) -> DateTime {
+        let mut datetime = DateTime(0);
+        datetime.set_year(year);
+        datetime.set_month(month);
+        datetime.set_day(day);
+        datetime.set_hour(hour);
+        datetime.set_minute(minute);
+        datetime.set_second(second);
+        datetime.set_micro(micro);
+        datetime.set_fsp(fsp);
+        datetime
+    }
+
+    /// The same date at `micros` past midnight, which must be less than a day.
+    fn at_time_of_day(mut self, micros: i64) -> DateTime {
+        let secs = (micros / MICROS_PER_SEC) as u64;
+        self.set_hour(secs / 3600);
+        self.set_minute(secs / 60 % 60);
+        self.set_second(secs % 60);
+        self.set_micro((micros % MICROS_PER_SEC) as u64);
+        self
+    }
+
+    /// Parse `YYYY-MM-DD[( |T)hh:mm:ss[.frac]]`, rounding the fraction to `fsp`. A fraction that
+    /// rounds up to the next second carries into the minute, the hour and the date.
+    pub fn parse(s: &[u8], fsp: i8) -> Result<DateTime> {
+        let fsp = check_fsp(fsp)?;
+        if s.is_empty() {
+            return Err(ParseError::EmptyInput);
+        }
+        let date_end = s
+            .iter()
+            .position(|&c| c == b' ' || c == b'T')
+            .unwrap_or(s.len());
+
+        let mut date = [0u64; 3];
+        let (mut part, mut digits) = (0, 0);
+        for (i, &c) in s[..date_end].iter().enumerate() {
+            if c.is_ascii_digit() && digits < if part == 0 { 4 } else { 2 } {
+                date[part] = date[part] * 10 + u64::from(c - b'0');
+                digits += 1;
+            } else if c == b'-' && digits > 0 && part < 2 {
+                part += 1;
+                digits = 0;
+            } else {
+                return Err(ParseError::InvalidCharacter(i));
+            }
+        }
+        if part < 2 || digits == 0 {
+            return Err(ParseError::InvalidCharacter(date_end));
+        }
+        let [year, month, day] = date;
+        check_date(year, month, day)?;
+
+        let mut time = [0u64; 3];
+        let mut time_starts = [s.len(); 3];
+        let mut micro = 0;
+        if date_end < s.len() {
+            let time_start = date_end + 1;
+            let time_end = s[time_start..]
+                .iter()
+                .position(|&c| c == b'.')
+                .map_or(s.len(), |dot| time_start + dot);
+            time_starts[0] = time_start;
+            let (mut part, mut digits) = (0, 0);
+            for (i, &c) in s[time_start..time_end].iter().enumerate() {
+                if c.is_ascii_digit() && digits < 2 {
+                    time[part] = time[part] * 10 + u64::from(c - b'0');
+                    digits += 1;
+                } else if c == b':' && digits > 0 && part < 2 {
+                    part += 1;
+                    digits = 0;
+                    time_starts[part] = time_start + i + 1;
+                } else {
+                    return Err(ParseError::InvalidCharacter(time_start + i));
+                }
+            }
+            if part < 2 || digits == 0 {
+                return Err(ParseError::InvalidCharacter(time_end));
+            }
+
+            // The first seven fraction digits are enough to round to six.
+            let (mut frac, mut frac_digits) = (0, 0);
+            for (i, &c) in s.iter().enumerate().skip(time_end + 1) {
+                if !c.is_ascii_digit() {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+                if frac_digits < 7 {
+                    frac = frac * 10 + u64::from(c - b'0');
+                    frac_digits += 1;
+                }
+            }
+            let unit = 10u64.pow(6 - u32::from(fsp));
+            micro = (frac * 10u64.pow(7 - frac_digits) / unit + 5) / 10 * unit;
+        }
+        let [hour, minute, second] = time;
+        if hour > 23 {
+            return Err(ParseError::HourOutOfRange(time_starts[0]));
+        }
+        if minute > 59 {
+            return Err(ParseError::MinuteOutOfRange(time_starts[1]));
+        }
+        if second > 59 {
+            return Err(ParseError::SecondOutOfRange(time_starts[2]));
+        }
+
+        let micros = ((hour * 60 + minute) * 60 + second) as i64 * MICROS_PER_SEC + micro as i64;
+        let date = DateTime::from_fields(year, month, day, 0, 0, 0, 0, fsp);
+        match date.to_micros() {
+            Some(midnight) => {
+                DateTime::from_micros(midnight + micros, fsp).ok_or(ParseError::InvalidDate(0))
+            }
+            // The zero date has no next day to carry into.
+            None if micros < MICROS_PER_DAY => Ok(date.at_time_of_day(micros)),
+            None => Err(ParseError::InvalidDate(0)),
+        }
+    }
+
+    /// Whether this is the zero date `0000-00-00`, on which no arithmetic is possible.
+    #[inline]
+    pub fn is_zero_date(&self) -> bool {
+        self.year() == 0 && self.month() == 0 && self.day() == 0
+    }
+
+    /// Microseconds since `1970-01-01 00:00:00`, `None` for the zero date.
+    fn to_micros(self) -> Option<i64> {
+        if self.is_zero_date() {
+            return None;
+        }
+        let days = days_from_civil(self.year() as i64, self.month(), self.day());
+        let secs = (self.hour() * 60 + self.minute()) * 60 + self.second();
+        Some(days * MICROS_PER_DAY + secs as i64 * MICROS_PER_SEC + self.micro() as i64)
+    }
+
+    /// The inverse of `to_micros`, `None` outside of years 1 to 9999.
+    fn from_micros(micros: i64, fsp: u8) -> Option<DateTime> {
+        let (days, time) = (
+            micros.div_euclid(MICROS_PER_DAY),
+            micros.rem_euclid(MICROS_PER_DAY),
+        );
+        let (year, month, day) = civil_from_days(days);
+        if !(1..=9999).contains(&year) {
+            return None;
+        }
+        Some(DateTime::from_fields(year as u64, month, day, 0, 0, 0, 0, fsp).at_time_of_day(time))
+    }
+
+    /// `TIMEDIFF(self, other)`. Like MySQL the difference is clamped to `±838:59:59`, and it is
+    /// `None` (SQL `NULL`) if either side is the zero date.
+    pub fn timediff(&self, other: &DateTime) -> Option<Duration> {
+        self.timediff_with_ctx(&mut EvalContext::default(), other)
+    }
+
+    /// `TIMEDIFF(self, other)` like `timediff`, raising `Warning::ClampedRange` into `ctx` if the
+    /// difference had to be clamped.
+    pub fn timediff_with_ctx(&self, ctx: &mut EvalContext, other: &DateTime) -> Option<Duration> {
+        let micros = self.to_micros()? - other.to_micros()?;
+        if micros.abs() > MAX_TIME_MICROS {
+            ctx.warn(Warning::ClampedRange);
+        }
+        let nanos = i128::from(micros.clamp(-MAX_TIME_MICROS, MAX_TIME_MICROS)) * 1000;
+        Duration::from_total_nanos(nanos, self.fsp().max(other.fsp()), ParseMode::Strict)
+    }
+
+    /// `ADDTIME(self, duration)`, `None` (SQL `NULL`) past the DATETIME range or for the zero
+    /// date.
+    pub fn checked_add_duration(&self, duration: Duration) -> Option<DateTime> {
+        let micros = self.to_micros()?.checked_add(duration.to_micros())?;
+        DateTime::from_micros(micros, self.fsp().max(duration.fsp()))
+    }
+
+    /// `self - duration`, like `SUBTIME(self, duration)`.
+    pub fn checked_sub_duration(&self, duration: Duration) -> Option<DateTime> {
+        self.checked_add_duration(duration.checked_neg()?)
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year(),
+            self.month(),
+            self.day(),
+            self.hour(),
+            self.minute(),
+            self.second()
+        )?;
+        match self.fsp() as usize {
+            0 => Ok(()),
+            fsp => write!(
+                f,
+                ".{:0width$}",
+                self.micro() / 10u64.pow(6 - fsp as u32),
+                width = fsp
+            ),
+        }
+    }
+}
+
+#[test]
+fn datetime() {
+    use crate::d;
+
+    let dt = |s: &str, fsp| DateTime::parse(s.as_bytes(), fsp).unwrap();
+
+    assert_eq!(
+        dt("2019-05-15 12:34:56.789", 2).to_string(),
+        "2019-05-15 12:34:56.79"
+    );
+    assert_eq!(dt("2019-5-1T1:2:3", 0).to_string(), "2019-05-01 01:02:03");
+    assert_eq!(dt("2020-02-29", 0).to_string(), "2020-02-29 00:00:00");
+    assert_eq!(
+        dt("0000-00-00 00:00:00", 0).to_string(),
+        "0000-00-00 00:00:00"
+    );
+    assert_eq!(
+        dt("2019-05-15 23:59:59.9", 0).to_string(),
+        "2019-05-16 00:00:00"
+    );
+    assert_eq!(
+        dt("2020-02-28 23:59:59.9999996", 6).to_string(),
+        "2020-02-29 00:00:00.000000"
+    );
+    assert_eq!(
+        dt("2019-05-15 1:2:3.45", 6).to_string(),
+        "2019-05-15 01:02:03.450000"
+    );
+
+    let errors = vec![
+        ("", ParseError::EmptyInput),
+        ("2019-05", ParseError::InvalidCharacter(7)),
+        ("2019-05-1x", ParseError::InvalidCharacter(9)),
+        ("2019-02-29", ParseError::InvalidDate(0)),
+        ("2019-05-15 24:00:00", ParseError::HourOutOfRange(11)),
+        ("2019-05-15 -1:00:00", ParseError::InvalidCharacter(11)),
+        ("2019-05-15 12:60:00", ParseError::MinuteOutOfRange(14)),
+        ("2019-05-15 12:00:60", ParseError::SecondOutOfRange(17)),
+        (
+            "2019-05-15 2020-01-01 12:00:00",
+            ParseError::InvalidCharacter(13),
+        ),
+        ("2019-05-15 12:00", ParseError::InvalidCharacter(16)),
+        ("2019-05-15 12:00:00.5x", ParseError::InvalidCharacter(21)),
+        ("9999-12-31 23:59:59.5", ParseError::InvalidDate(0)),
+        ("0000-00-00 23:59:59.5", ParseError::InvalidDate(0)),
+    ];
+    for (input, err) in errors {
+        assert_eq!(
+            DateTime::parse(input.as_bytes(), 0).unwrap_err(),
+            err,
+            "{}",
+            input
+        );
+    }
+
+    let diff = dt("2019-05-15 12:00:00", 0).timediff(&dt("2019-05-14 13:30:00.5", 1));
+    assert_eq!(diff.unwrap().to_string(), "22:29:59.5");
+    let diff = dt("2000-01-01 00:00:00", 0).timediff(&dt("2000-03-01 00:00:00", 0));
+    assert_eq!(diff.unwrap().to_string(), "-838:59:59");
+    let mut ctx = EvalContext::default();
+    dt("2000-03-01", 0).timediff_with_ctx(&mut ctx, &dt("2000-01-01", 0));
+    assert_eq!(ctx.warnings(), &[Warning::ClampedRange]);
+    assert!(dt("0000-00-00", 0).timediff(&dt("2000-01-01", 0)).is_none());
+
+    let sum = dt("2019-12-31 23:00:00", 0).checked_add_duration(d("1:00:00.25", 2));
+    assert_eq!(sum.unwrap().to_string(), "2020-01-01 00:00:00.25");
+    let diff = dt("2020-03-01 00:00:00", 0).checked_sub_duration(d("0:0:1", 0));
+    assert_eq!(diff.unwrap().to_string(), "2020-02-29 23:59:59");
+    let diff = dt("2019-05-15 00:00:00", 0).checked_sub_duration(d("-838:59:59", 0));
+    assert_eq!(diff.unwrap().to_string(), "2019-06-18 22:59:59");
+    assert!(dt("9999-12-31 23:59:59", 0)
+        .checked_add_duration(d("0:0:1", 0))
+        .is_none());
+    assert!(dt("0001-01-01 00:00:00", 0)
+        .checked_sub_duration(d("0:0:1", 0))
+        .is_none());
+}
@@ -0,0 +1,132 @@
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
+use crate::arith::MAX_NANOS;
+use crate::codec::SIGN_MASK;
+use crate::{check_fsp, Duration, COMPARABLE_LEN};
+
+/// The flag byte TiDB puts in front of a TIME datum.
+pub const DURATION_FLAG: u8 = 7;
+/// The length of an encoded TIME datum, the flag followed by the nanoseconds.
+pub const DATUM_LEN: usize = 1 + COMPARABLE_LEN;
+
+/// The reason why a TIME datum or chunk column could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatumError {
+    /// The input ended after the given number of bytes, before the end of the datum.
+    Truncated(usize),
+    /// A chunk buffer of the given length does not fit the number of values of the column.
+    InvalidLength(usize),
+    /// The datum starts with the given flag rather than `DURATION_FLAG`.
+    UnexpectedFlag(u8),
+    /// The nanoseconds lie outside of `[-838:59:59, 838:59:59]`.
+    OutOfRange(i64),
+    /// The column fsp is not within `[0, 6]` or `UNSPECIFIED_FSP`.
+    InvalidFsp(i8),
+}
+
+impl fmt::Display for DatumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DatumError::Truncated(len) => write!(f, "datum truncated after {} bytes", len),
+            DatumError::InvalidLength(len) => write!(f, "invalid chunk buffer length {}", len),
+            DatumError::UnexpectedFlag(flag) => write!(f, "unexpected datum flag {}", flag),
+            DatumError::OutOfRange(nanos) => write!(f, "{} nanoseconds out of range", nanos),
+            DatumError::InvalidFsp(fsp) => write!(f, "invalid fsp {}", fsp),
+        }
+    }
+}
+
+impl Error for DatumError {}
+
+impl Duration {
+    /// Append the value as a TiDB datum to `buf`: `DURATION_FLAG` followed by the signed
+    /// nanoseconds as a big-endian `i64` with the sign bit flipped. The fsp is not encoded, it
+    /// comes from the column when decoding.
+    pub fn encode_datum(&self, buf: &mut Vec<u8>) {
+        buf.push(DURATION_FLAG);
+        self.encode_comparable(buf);
+    }
+
+    /// Decode a datum written by `encode_datum` from the start of `data`, rounding it to the
+    /// column `fsp`, and return it along with the number of bytes read.
+    pub fn decode_datum(data: &[u8], fsp: i8) -> Result<(Duration, usize), DatumError> {
+        check_fsp(fsp).map_err(|_| DatumError::InvalidFsp(fsp))?;
+        match data.first() {
+            Some(&DURATION_FLAG) => {}
+            Some(&flag) => return Err(DatumError::UnexpectedFlag(flag)),
+            None => return Err(DatumError::Truncated(0)),
+        }
+        let key = match data.get(1..DATUM_LEN) {
+            Some(key) => u64::from_be_bytes(key.try_into().unwrap()),
+            None => return Err(DatumError::Truncated(data.len())),
+        };
+        // Not `decode_comparable`, which would round to `MAX_FSP` before the column fsp.
+        let duration = Duration::from_stored_nanos((key ^ SIGN_MASK) as i64, fsp)?;
+        Ok((duration, DATUM_LEN))
+    }
+
+    /// Build a decoded value from its nanoseconds, rounded to a column fsp that was checked.
+    pub(crate) fn from_stored_nanos(nanos: i64, fsp: i8) -> Result<Duration, DatumError> {
+        if u128::from(nanos.unsigned_abs()) > MAX_NANOS {
+            return Err(DatumError::OutOfRange(nanos));
+        }
+        Duration::from_nanos(nanos, fsp).map_err(|_| DatumError::OutOfRange(nanos))
+    }
+}
+
+#[test]
+fn datum() {
+    use crate::{d, encoded};
+
+    let encode = |d: &Duration| encoded(Duration::encode_datum, d);
+
+    let cases = vec![
+        ("0", 0),
+        ("12:34:56", 0),
+        ("-12:34:56.5", 1),
+        ("838:59:59", 0),
+        ("-838:59:59", 6),
+        ("0:0:0.000001", 6),
+    ];
+    for (input, fsp) in cases {
+        let value = d(input, fsp);
+        let buf = encode(&value);
+        assert_eq!(buf.len(), DATUM_LEN);
+        assert_eq!(buf[0], DURATION_FLAG);
+        let (decoded, read) = Duration::decode_datum(&buf, fsp).unwrap();
+        assert_eq!(read, DATUM_LEN);
+        assert_eq!(decoded.to_string(), value.to_string());
+        assert_eq!(decoded.fsp(), value.fsp());
+    }
+
+    // The fsp comes from the column, and the nanoseconds are rounded to it.
+    let buf = encode(&d("1:02:03.456", 3));
+    let (decoded, _) = Duration::decode_datum(&buf, 1).unwrap();
+    assert_eq!(decoded.to_string(), "01:02:03.5");
+    let (decoded, _) = Duration::decode_datum(&buf, 6).unwrap();
+    assert_eq!(decoded.to_string(), "01:02:03.456000");
+
+    let mut buf = encode(&d("1:00:00", 0));
+    buf.extend_from_slice(&[1, 2, 3]);
+    assert_eq!(Duration::decode_datum(&buf, 0).unwrap().1, DATUM_LEN);
+
+    let too_large = 839 * 3600 * 1_000_000_000i64;
+    let mut out_of_range = vec![DURATION_FLAG];
+    out_of_range.extend_from_slice(&(too_large as u64 ^ SIGN_MASK).to_be_bytes());
+    let errors = vec![
+        (vec![], 0, DatumError::Truncated(0)),
+        (buf[..4].to_vec(), 0, DatumError::Truncated(4)),
+        (
+            vec![8, 0, 0, 0, 0, 0, 0, 0, 0],
+            0,
+            DatumError::UnexpectedFlag(8),
+        ),
+        (out_of_range, 0, DatumError::OutOfRange(too_large)),
+        (buf.clone(), 7, DatumError::InvalidFsp(7)),
+    ];
+    for (data, fsp, err) in errors {
+        assert_eq!(Duration::decode_datum(&data, fsp).unwrap_err(), err);
+    }
+}
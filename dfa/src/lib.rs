@@ -1,6 +1,34 @@
 #![feature(test)]
 extern crate test;
 use bitfield::bitfield;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::time::Duration as StdDuration;
+
+use duration_common::{
+    micro_bits_to_nano_bits, nano_bits_to_micro_bits, split_time_number, Decimal,
+};
+
+mod arith;
+mod batch;
+mod chunk;
+mod codec;
+mod datetime;
+mod datum;
+mod parser;
+mod row;
+mod simd;
+mod time_format;
+
+pub use batch::{is_valid, StrColumn};
+pub use chunk::DurationColumn;
+pub use codec::COMPARABLE_LEN;
+pub use datetime::DateTime;
+pub use datum::{DatumError, DATUM_LEN, DURATION_FLAG};
+pub use duration_common::{EvalContext, ParseError, ParseMode, TimeFields, TimeStr, Warning};
+pub use parser::DurationParser;
 
 const TEN_POW: &[u32] = &[
     1, 10, 100, 1000, 10000, 100000, 1000000, 10000000, 100000000, 1000000000,
@@ -18,32 +46,77 @@ const MAX_SECONDS: u64 = 59;
 const MINUTES_PER_HOUR: u64 = 60;
 const SECS_PER_MINUTE: u64 = 60;
 
-type Result<T> = std::result::Result<T, ()>;
+type Result<T> = std::result::Result<T, ParseError>;
 
 bitfield! {
     #[derive(Clone, Copy)]
     pub struct Duration(u64);
     impl Debug;
     #[inline]
-    bool, neg, set_neg: 63;
+    pub bool, neg, _: 63;
+    #[inline]
+    bool, _, set_neg: 63;
+    #[inline]
+    pub u64, hour, _: 62, 53;
+    #[inline]
+    u64, _, set_hour: 62, 53;
+    #[inline]
+    pub u64, minute, _: 52, 47;
     #[inline]
-    u64, hour, set_hour: 62, 53;
+    u64, _, set_minute: 52, 47;
     #[inline]
-    u64, minute, set_minute: 52, 47;
+    pub u64, second, _: 46, 41;
     #[inline]
-    u64, second, set_second: 46, 41;
+    u64, _, set_second: 46, 41;
+    /// The fraction in nanoseconds.
     #[inline]
-    u64, nano, set_nano: 40, 9;
+    pub u64, nano, _: 40, 9;
+    #[inline]
+    u64, _, set_nano: 40, 9;
     #[inline]
     pub u8, fsp, set_fsp: 8, 1;
     #[inline]
     bool, unused, set_unused: 0;
 }
 
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_time_str(), f)
+    }
+}
+
+/// Values are compared by what they represent: `fsp` is ignored and negative values sort below
+/// positive ones.
+impl PartialEq for Duration {
+    fn eq(&self, other: &Duration) -> bool {
+        self.to_nanos() == other.to_nanos()
+    }
+}
+
+impl Eq for Duration {}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Duration) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Duration) -> Ordering {
+        self.to_nanos().cmp(&other.to_nanos())
+    }
+}
+
+impl Hash for Duration {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_nanos().hash(state);
+    }
+}
+
 #[inline]
 fn check_hour(hour: u64) -> Result<u64> {
     if hour > MAX_HOURS {
-        Err(())
+        Err(ParseError::HourOutOfRange(0))
     } else {
         Ok(hour)
     }
@@ -52,7 +125,7 @@ fn check_hour(hour: u64) -> Result<u64> {
 #[inline]
 fn check_minute(minute: u64) -> Result<u64> {
     if minute > MAX_MINUTES {
-        Err(())
+        Err(ParseError::MinuteOutOfRange(0))
     } else {
         Ok(minute)
     }
@@ -61,19 +134,26 @@ fn check_minute(minute: u64) -> Result<u64> {
 #[inline]
 fn check_second(second: u64) -> Result<u64> {
     if second > MAX_SECONDS {
-        Err(())
+        Err(ParseError::SecondOutOfRange(0))
     } else {
         Ok(second)
     }
 }
 
+/// Whether the components exceed the largest TIME value `838:59:59`.
+#[inline]
+fn exceeds_max(hour: u64, minute: u64, second: u64, nano: u64) -> bool {
+    hour > MAX_HOURS
+        || hour == MAX_HOURS && minute == MAX_MINUTES && second == MAX_SECONDS && nano > 0
+}
+
 #[inline]
 fn check_fsp(fsp: i8) -> Result<u8> {
     if fsp == UNSPECIFIED_FSP {
         return Ok(DEFAULT_FSP as u8);
     }
-    if fsp > MAX_FSP || fsp < MIN_FSP {
-        return Err(());
+    if !(MIN_FSP..=MAX_FSP).contains(&fsp) {
+        return Err(ParseError::InvalidFsp(fsp));
     }
     Ok(fsp as u8)
 }
@@ -87,11 +167,14 @@ struct DurationBuilder {
     nano: u64,
     fsp: u8,
     round_with_fsp: bool,
+    mode: ParseMode,
 }
 
 impl DurationBuilder {
     pub fn check(self) -> Result<Self> {
-        check_hour(self.hour)?;
+        if self.mode == ParseMode::Strict {
+            check_hour(self.hour)?;
+        }
         check_minute(self.minute)?;
         check_second(self.second)?;
         check_fsp(self.fsp as i8)?;
@@ -104,8 +187,51 @@ impl Duration {
     pub fn zero() -> Self {
         Duration(0)
     }
+
+    /// The packed representation, for persisting the value.
     #[inline]
-    fn build(builder: DurationBuilder) -> Result<Duration> {
+    pub fn to_bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Restore a value persisted with `to_bits`, rejecting bit patterns no valid value has.
+    pub fn from_bits(bits: u64) -> Result<Duration> {
+        TimeFields::from_nano_bits(bits)?;
+        Ok(Duration(bits))
+    }
+
+    /// The bits of the same value in the microsecond layout of duration-nom.
+    pub fn to_micro_bits(&self) -> Result<u64> {
+        nano_bits_to_micro_bits(self.0)
+    }
+
+    /// Restore a value persisted in the microsecond layout of duration-nom.
+    pub fn from_micro_bits(bits: u64) -> Result<Duration> {
+        micro_bits_to_nano_bits(bits).map(Duration)
+    }
+
+    /// Append the value in MySQL's `[-]hh:mm:ss[.fff]` format, like `Display` but without
+    /// allocating.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.to_time_str().as_bytes());
+    }
+
+    /// The value in MySQL's `[-]hh:mm:ss[.fff]` format, with exactly `fsp` fraction digits.
+    #[inline]
+    pub fn to_time_str(&self) -> TimeStr {
+        TimeStr::new(
+            self.neg(),
+            self.hour(),
+            self.minute(),
+            self.second(),
+            self.nano(),
+            self.fsp(),
+        )
+    }
+    /// Build a `Duration` and report whether it had to be clamped to the TIME range, which only
+    /// happens with `ParseMode::Truncate`.
+    #[inline]
+    fn build(builder: DurationBuilder) -> Result<(Duration, bool)> {
         let DurationBuilder {
             neg,
             mut hour,
@@ -114,6 +240,7 @@ impl Duration {
             mut nano,
             fsp,
             round_with_fsp,
+            mode,
         } = builder.check()?;
 
         if round_with_fsp {
@@ -127,13 +254,23 @@ impl Duration {
             second += nano / NANOS_PER_SEC;
             minute += second / SECS_PER_MINUTE;
             hour += minute / MINUTES_PER_HOUR;
-            hour = check_hour(hour)?;
 
             nano %= NANOS_PER_SEC;
             second %= SECS_PER_MINUTE;
             minute %= MINUTES_PER_HOUR;
         }
 
+        let clamped = exceeds_max(hour, minute, second, nano);
+        if clamped {
+            if mode == ParseMode::Strict {
+                return Err(ParseError::HourOutOfRange(0));
+            }
+            hour = MAX_HOURS;
+            minute = MAX_MINUTES;
+            second = MAX_SECONDS;
+            nano = 0;
+        }
+
         let mut duration = Duration(0);
         duration.set_neg(neg);
         duration.set_hour(hour);
@@ -141,214 +278,196 @@ impl Duration {
         duration.set_second(second);
         duration.set_nano(nano);
         duration.set_fsp(fsp);
-        Ok(duration)
+        Ok((duration, clamped))
     }
 
     pub fn parse(s: &[u8], fsp: i8) -> Result<Duration> {
-        use State::*;
-        #[derive(PartialEq, Debug)]
-        enum State {
-            Start,
-            Block,
-            PostBlock,
-            Hour,
-            MinuteColon,
-            Minute,
-            SecondColon,
-            Second,
-            Dot,
-            Fraction,
-            Consume,
-            End,
+        Duration::parse_with_ctx(&mut EvalContext::default(), s, fsp)
+    }
+
+    /// Parse a TIME value like `parse`, but with `ParseMode::Truncate` values out of the TIME
+    /// range are clamped to `[-838:59:59, 838:59:59]` instead of rejected. The returned flag
+    /// tells whether that happened, so the caller can raise a warning.
+    pub fn parse_with_mode(s: &[u8], fsp: i8, mode: ParseMode) -> Result<(Duration, bool)> {
+        let mut ctx = EvalContext::new(mode);
+        let duration = Duration::parse_with_ctx(&mut ctx, s, fsp)?;
+        Ok((duration, ctx.count(Warning::ClampedRange) > 0))
+    }
+
+    /// Parse a TIME value in the `ParseMode` of `ctx`, recording the warnings raised on the
+    /// way into it. Nothing is recorded if parsing fails.
+    ///
+    /// Besides the TIME formats, DATETIME-shaped inputs `YYYY-MM-DD hh:mm:ss[.frac]` and
+    /// `YYYYMMDDhhmmss[.frac]` are accepted, of which only the time of day is kept.
+    pub fn parse_with_ctx(ctx: &mut EvalContext, s: &[u8], fsp: i8) -> Result<Duration> {
+        if let Some(duration) = simd::parse_canonical(s, check_fsp(fsp)?) {
+            return Ok(duration);
         }
+        let mut parser = DurationParser::with_mode(fsp, ctx.mode)?;
+        parser.feed(s)?;
+        parser.finish_with_ctx(ctx)
+    }
 
+    /// Convert an integer to a TIME value following MySQL's numeric rules: it is read as
+    /// `[-]hhmmss`, or when it is too large for that, as a `YYYYMMDDhhmmss` (or `YYMMDDhhmmss`)
+    /// datetime number of which only the time of day is kept.
+    pub fn from_i64(value: i64, fsp: i8) -> Result<Duration> {
         let fsp = check_fsp(fsp)?;
-        let check_block = |block| if block > 8385959 { Err(()) } else { Ok(block) };
-        let to_dec = |d| u64::from(d - b'0');
-
-        let mut neg = false;
-        let (mut block, mut day, mut hour, mut minute, mut second, mut fract) = (0, 0, 0, 0, 0, 0);
-        let mut eaten = 0;
-
-        let mut state = Start;
-        for &c in s {
-            if c == b'.'
-                && (state == Start
-                    || state == Block
-                    || state == PostBlock
-                    || state == Hour
-                    || state == Minute
-                    || state == Second)
-            {
-                state = Dot;
-                continue;
-            }
-            state = match state {
-                Start => {
-                    if c.is_ascii_digit() {
-                        block = to_dec(c);
-                        Block
-                    } else if c.is_ascii_whitespace() {
-                        Start
-                    } else if c == b'-' {
-                        if neg {
-                            return Err(());
-                        } else {
-                            neg = true;
-                            Start
-                        }
-                    } else {
-                        return Err(());
-                    }
-                }
-                Block => {
-                    if c.is_ascii_digit() {
-                        block = check_block(block * 10 + to_dec(c))?;
-                        Block
-                    } else if c.is_ascii_whitespace() {
-                        PostBlock
-                    } else if c == b':' {
-                        hour = block;
-                        block = 0;
-                        MinuteColon
-                    } else {
-                        return Err(());
-                    }
-                }
-                PostBlock => {
-                    if c.is_ascii_digit() {
-                        hour = to_dec(c);
-                        day = block;
-                        block = 0;
-                        Hour
-                    } else if c.is_ascii_whitespace() {
-                        PostBlock
-                    } else {
-                        return Err(());
-                    }
-                }
-                Hour => {
-                    if c.is_ascii_digit() {
-                        hour = check_hour(hour * 10 + to_dec(c))?;
-                        Hour
-                    } else if c.is_ascii_whitespace() {
-                        End
-                    } else if c == b':' {
-                        MinuteColon
-                    } else {
-                        return Err(());
-                    }
-                }
-                MinuteColon => {
-                    if c.is_ascii_digit() {
-                        minute = to_dec(c);
-                        Minute
-                    } else {
-                        return Err(());
-                    }
-                }
-                Minute => {
-                    if c.is_ascii_digit() {
-                        minute = check_minute(minute * 10 + to_dec(c))?;
-                        Minute
-                    } else if c.is_ascii_whitespace() {
-                        End
-                    } else if c == b':' {
-                        SecondColon
-                    } else {
-                        return Err(());
-                    }
-                }
-                SecondColon => {
-                    if c.is_ascii_digit() {
-                        second = to_dec(c);
-                        Second
-                    } else {
-                        return Err(());
-                    }
-                }
-                Second => {
-                    if c.is_ascii_digit() {
-                        second = check_second(second * 10 + to_dec(c))?;
-                        Second
-                    } else if c.is_ascii_whitespace() {
-                        End
-                    } else {
-                        return Err(());
-                    }
-                }
-                Dot => {
-                    if c.is_ascii_digit() {
-                        if fsp == 0 {
-                            if to_dec(c) > 4 {
-                                fract = 1;
-                            }
-                            Consume
-                        } else {
-                            fract = to_dec(c);
-                            eaten = 1;
-                            Fraction
-                        }
-                    } else {
-                        return Err(());
-                    }
-                }
-                Fraction => {
-                    if c.is_ascii_digit() {
-                        if eaten < fsp {
-                            fract = fract * 10 + to_dec(c);
-                            eaten += 1;
-                            Fraction
-                        } else {
-                            if to_dec(c) > 4 {
-                                fract += 1;
-                            }
-                            Consume
-                        }
-                    } else if c.is_ascii_whitespace() {
-                        End
-                    } else {
-                        return Err(());
-                    }
-                }
-                Consume => {
-                    if c.is_ascii_digit() {
-                        Consume
-                    } else if c.is_ascii_whitespace() {
-                        End
-                    } else {
-                        return Err(());
-                    }
-                }
-                End => {
-                    if c.is_ascii_whitespace() {
-                        End
-                    } else {
-                        return Err(());
-                    }
-                }
-            };
-        }
-        if state == MinuteColon || state == SecondColon {
-            return Err(());
-        }
-        if block != 0 {
-            second = block % 100;
-            minute = block / 100 % 100;
-            hour = block / 10000;
+        Duration::from_decimal(
+            Decimal {
+                neg: value < 0,
+                integer: value.unsigned_abs(),
+                nanos: 0,
+            },
+            fsp,
+        )
+    }
+
+    /// Convert a float to a TIME value like `from_i64`, rounding the fraction to `fsp`.
+    pub fn from_f64(value: f64, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        Duration::from_decimal(Decimal::from_f64(value)?, fsp)
+    }
+
+    /// Convert the text of a decimal like `123456.789` or `1.23456789e5` to a TIME value like
+    /// `from_i64`, rounding the fraction to `fsp`.
+    pub fn from_decimal_str(s: &str, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        Duration::from_decimal(Decimal::parse(s.as_bytes())?, fsp)
+    }
+
+    fn from_decimal(decimal: Decimal, fsp: u8) -> Result<Duration> {
+        let Decimal {
+            neg,
+            integer,
+            nanos,
+        } = decimal;
+        let number = split_time_number(neg, integer)?;
+        Duration::build(DurationBuilder {
+            neg,
+            hour: number / 10000,
+            minute: number / 100 % 100,
+            second: number % 100,
+            nano: nanos,
+            fsp,
+            round_with_fsp: true,
+            mode: ParseMode::Strict,
+        })
+        .map(|(duration, _)| duration.clear_neg_if_zero())
+    }
+
+    /// Convert a `std::time::Duration` and its sign, rounding the fraction to `fsp`.
+    pub fn from_std(duration: StdDuration, neg: bool, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        let secs = duration.as_secs();
+        Duration::build(DurationBuilder {
+            neg,
+            hour: secs / (SECS_PER_MINUTE * MINUTES_PER_HOUR),
+            minute: secs / SECS_PER_MINUTE % MINUTES_PER_HOUR,
+            second: secs % SECS_PER_MINUTE,
+            nano: u64::from(duration.subsec_nanos()),
+            fsp,
+            round_with_fsp: true,
+            mode: ParseMode::Strict,
+        })
+        .map(|(duration, _)| duration.clear_neg_if_zero())
+    }
+
+    /// The absolute value as a `std::time::Duration`, and whether it is negative.
+    pub fn to_std(&self) -> (StdDuration, bool) {
+        let secs =
+            (self.hour() * MINUTES_PER_HOUR + self.minute()) * SECS_PER_MINUTE + self.second();
+        (StdDuration::new(secs, self.nano() as u32), self.neg())
+    }
+
+    /// Build a value from its components, rejecting any of them out of range. `micros` is the
+    /// fraction in microseconds and gets rounded to `fsp`.
+    pub fn from_parts(
+        neg: bool,
+        hour: u64,
+        minute: u64,
+        second: u64,
+        micros: u64,
+        fsp: i8,
+    ) -> Result<Duration> {
+        if micros >= NANOS_PER_SEC / 1000 {
+            return Err(ParseError::Overflow(0));
         }
-        hour += day * 24;
-        fract *= u64::from(TEN_POW[NANO_WIDTH as usize - eaten as usize]);
         Duration::build(DurationBuilder {
             neg,
             hour,
             minute,
             second,
-            nano: fract,
-            fsp,
-            round_with_fsp: false,
+            nano: micros * 1000,
+            fsp: check_fsp(fsp)?,
+            round_with_fsp: true,
+            mode: ParseMode::Strict,
         })
+        .map(|(duration, _)| duration.clear_neg_if_zero())
     }
+
+    /// The value as a signed number of nanoseconds.
+    pub fn to_nanos(&self) -> i64 {
+        let nanos = self.to_std().0.as_nanos() as i64;
+        if self.neg() {
+            -nanos
+        } else {
+            nanos
+        }
+    }
+
+    /// The value as a signed number of microseconds, anything finer is dropped.
+    pub fn to_micros(&self) -> i64 {
+        self.to_nanos() / 1000
+    }
+
+    /// The value as a `[-]hhmmss` number like MySQL's `time_col + 0` in an integer context, with
+    /// the fraction rounded to the nearest second.
+    pub fn to_i64_hhmmss(&self) -> i64 {
+        let (std, neg) = self.to_std();
+        let secs = std.as_secs() + u64::from(std.subsec_nanos() >= NANOS_PER_SEC as u32 / 2);
+        let hhmmss = (secs / 3600 * 100 + secs / 60 % 60) * 100 + secs % 60;
+        if neg {
+            -(hhmmss as i64)
+        } else {
+            hhmmss as i64
+        }
+    }
+
+    /// The value as a `[-]hhmmss.fff` number with `fsp` fraction digits.
+    pub fn to_f64(&self) -> f64 {
+        let (hhmmss, frac) = self.hhmmss_frac();
+        let value = hhmmss as f64 + frac as f64 / f64::from(TEN_POW[self.fsp() as usize]);
+        if self.neg() {
+            -value
+        } else {
+            value
+        }
+    }
+
+    /// The value as the text of a `[-]hhmmss.fff` decimal with exactly `fsp` fraction digits,
+    /// like MySQL's `time_col + 0`.
+    pub fn to_decimal_string(&self) -> String {
+        let (hhmmss, frac) = self.hhmmss_frac();
+        let sign = if self.clear_neg_if_zero().neg() {
+            "-"
+        } else {
+            ""
+        };
+        match self.fsp() as usize {
+            0 => format!("{}{}", sign, hhmmss),
+            fsp => format!("{}{}.{:0width$}", sign, hhmmss, frac, width = fsp),
+        }
+    }
+
+    /// The `hhmmss` number and the fraction cut to `fsp` digits.
+    #[inline]
+    fn hhmmss_frac(&self) -> (u64, u64) {
+        let hhmmss = self.hour() * 10000 + self.minute() * 100 + self.second();
+        let frac = self.nano() / u64::from(TEN_POW[NANO_WIDTH as usize - self.fsp() as usize]);
+        (hhmmss, frac)
+    }
+
     pub fn round_frac(mut self, fsp: i8) -> Result<Self> {
         let fsp = check_fsp(fsp)?;
         if fsp >= self.fsp() {
@@ -364,10 +483,42 @@ impl Duration {
             nano: self.nano(),
             fsp,
             round_with_fsp: true,
+            mode: ParseMode::Strict,
         })
+        .map(|(duration, _)| duration)
     }
 }
 
+duration_common::impl_parse_prefix!(Duration);
+duration_common::impl_signed_conversions!(Duration);
+#[cfg(feature = "serde")]
+duration_common::impl_serde!(Duration);
+#[cfg(all(test, feature = "serde"))]
+duration_common::serde_round_trip_test!(Duration);
+
+/// Keeps the value down to microseconds, the finest precision of a TIME value.
+impl TryFrom<(StdDuration, bool)> for Duration {
+    type Error = ParseError;
+
+    fn try_from((duration, neg): (StdDuration, bool)) -> Result<Duration> {
+        Duration::from_std(duration, neg, MAX_FSP)
+    }
+}
+
+/// Parse a value the test knows to be valid.
+#[cfg(test)]
+fn d(s: &str, fsp: i8) -> Duration {
+    Duration::parse(s.as_bytes(), fsp).unwrap()
+}
+
+/// The bytes `encode` appends to an empty buffer for `d`.
+#[cfg(test)]
+fn encoded(encode: fn(&Duration, &mut Vec<u8>), d: &Duration) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode(d, &mut buf);
+    buf
+}
+
 #[test]
 fn dbg_parse() {
     match Duration::parse(b"11:30:45.123456", 6) {
@@ -380,9 +531,506 @@ fn dbg_parse() {
     };
 }
 
+#[test]
+fn parse_error() {
+    for &(s, fsp, err) in duration_common::testdata::PARSE_ERRORS {
+        assert_eq!(
+            Duration::parse(s.as_bytes(), fsp).unwrap_err(),
+            err,
+            "{}",
+            s
+        );
+    }
+}
+
+#[test]
+fn parse_with_mode() {
+    let cases: &[(&[u8], i8, &str, bool)] = &[
+        (b"839:00:00", 0, "838:59:59.0", true),
+        (b"-1000:00:00.5", 1, "-838:59:59.0", true),
+        (b"35 00:00:00", 0, "838:59:59.0", true),
+        (b"99999999", 0, "838:59:59.0", true),
+        (b"838:59:59.4", 0, "838:59:59.0", false),
+        (b"838:59:59.5", 0, "838:59:59.0", true),
+        (b"12:34:56.7", 1, "12:34:56.700000000", false),
+    ];
+    for &(s, fsp, expected, clamped) in cases {
+        let (duration, truncated) = Duration::parse_with_mode(s, fsp, ParseMode::Truncate).unwrap();
+        let actual = format!(
+            "{}{}:{:02}:{:02}.{}",
+            if duration.neg() { "-" } else { "" },
+            duration.hour(),
+            duration.minute(),
+            duration.second(),
+            duration.nano()
+        );
+        assert_eq!(actual, expected, "{}", String::from_utf8_lossy(s));
+        assert_eq!(truncated, clamped);
+        if clamped {
+            assert!(Duration::parse(s, fsp).is_err());
+        }
+    }
+    assert_eq!(
+        Duration::parse_with_mode(b"12:61:00", 0, ParseMode::Truncate).unwrap_err(),
+        ParseError::MinuteOutOfRange(3)
+    );
+}
+
+#[test]
+fn parse_with_ctx() {
+    let mut ctx = EvalContext::new(ParseMode::Truncate);
+    Duration::parse_with_ctx(&mut ctx, b"12:34:56.7891", 3).unwrap();
+    Duration::parse_with_ctx(&mut ctx, b"12:34:56.7890", 3).unwrap();
+    Duration::parse_with_ctx(&mut ctx, b"900:00:00.5", 0).unwrap();
+    Duration::parse_with_ctx(&mut ctx, b"12:34:56.7x", 0).unwrap_err();
+    assert_eq!(
+        ctx.warnings(),
+        &[
+            Warning::TruncatedFraction,
+            Warning::TruncatedFraction,
+            Warning::ClampedRange
+        ]
+    );
+}
+
+#[test]
+fn parse_warnings() {
+    for &(s, fsp, mode, len, warnings) in duration_common::testdata::PARSE_WARNINGS {
+        let mut ctx = EvalContext::new(mode);
+        let (_, read) = Duration::parse_prefix_with_ctx(&mut ctx, s.as_bytes(), fsp).unwrap();
+        assert_eq!((read, ctx.warnings()), (len, warnings), "{}", s);
+    }
+}
+
+#[test]
+fn parse_datetime() {
+    let cases: &[(&[u8], i8, [u64; 4])] = &[
+        (b"2019-05-15 12:34:56.789", 3, [12, 34, 56, 789_000_000]),
+        (b"2019-05-15T12:34:56", 0, [12, 34, 56, 0]),
+        (b"  2020-2-29 1:2:3  ", 0, [1, 2, 3, 0]),
+        (b"20190515123456.5", 0, [12, 34, 57, 0]),
+        (b"20190515000000", 0, [0, 0, 0, 0]),
+        (b"0000-00-00 12:00:00", 0, [12, 0, 0, 0]),
+    ];
+    for &(s, fsp, expected) in cases {
+        let duration = Duration::parse(s, fsp).unwrap();
+        assert_eq!(
+            [
+                duration.hour(),
+                duration.minute(),
+                duration.second(),
+                duration.nano()
+            ],
+            expected,
+            "{}",
+            String::from_utf8_lossy(s)
+        );
+    }
+
+    let mut ctx = EvalContext::default();
+    Duration::parse_with_ctx(&mut ctx, b"0000-00-00 12:00:00", 0).unwrap();
+    assert_eq!(ctx.warnings(), &[Warning::ZeroDate]);
+
+    let cases: &[(&[u8], ParseError)] = &[
+        (b"2019-13-01 12:00:00", ParseError::InvalidDate(0)),
+        (b"2019-02-29 12:00:00", ParseError::InvalidDate(0)),
+        (b"20190230120000", ParseError::InvalidDate(0)),
+        (b"2019-05-15 24:00:00", ParseError::HourOutOfRange(11)),
+        (b"2019-05-15", ParseError::InvalidCharacter(10)),
+        (b"-2019-05-15 12:00:00", ParseError::InvalidCharacter(5)),
+        (b"201-05-15 12:00:00", ParseError::InvalidCharacter(3)),
+        (b"123456789012345", ParseError::HourOutOfRange(0)),
+    ];
+    for &(s, err) in cases {
+        assert_eq!(Duration::parse(s, 0).unwrap_err(), err);
+    }
+}
+
+#[test]
+fn from_i64() {
+    let cases = vec![
+        (0, "0:00:00"),
+        (123456, "12:34:56"),
+        (-8385959, "-838:59:59"),
+        (20190515123456, "12:34:56"),
+        (190515123456, "12:34:56"),
+    ];
+    for (value, expected) in cases {
+        let duration = Duration::from_i64(value, 0).unwrap();
+        let actual = format!(
+            "{}{}:{:02}:{:02}",
+            if duration.neg() { "-" } else { "" },
+            duration.hour(),
+            duration.minute(),
+            duration.second()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    let cases = vec![
+        (8385960, ParseError::Overflow(0)),
+        (-8390000, ParseError::Overflow(0)),
+        (126000, ParseError::MinuteOutOfRange(0)),
+        (123460, ParseError::SecondOutOfRange(0)),
+        (20191315123456, ParseError::InvalidDate(0)),
+        (20190515243456, ParseError::HourOutOfRange(0)),
+    ];
+    for (value, err) in cases {
+        assert_eq!(Duration::from_i64(value, 0).unwrap_err(), err);
+    }
+}
+
+#[test]
+fn parse_prefix() {
+    let cases = vec![
+        ("12:34:56abc", 6, 8, "12:34:56.000000"),
+        ("12:34:56.789x", 2, 12, "12:34:56.790000"),
+        ("12:34:", 0, 5, "12:34:00.000000"),
+        ("2019-05-15 12:34:56 PM", 0, 20, "12:34:56.000000"),
+        ("12:34:56", 0, 8, "12:34:56.000000"),
+    ];
+    for (input, fsp, consumed, expected) in cases {
+        let mut ctx = EvalContext::default();
+        let (duration, len) = Duration::parse_prefix_with_ctx(&mut ctx, input.as_bytes(), fsp)
+            .unwrap_or_else(|e| panic!("{}: {}", input, e));
+        let actual = format!(
+            "{:02}:{:02}:{:02}.{:06}",
+            duration.hour(),
+            duration.minute(),
+            duration.second(),
+            duration.nano() / 1000
+        );
+        assert_eq!((len, actual.as_str()), (consumed, expected), "{}", input);
+        assert_eq!(
+            ctx.count(Warning::TruncatedTrailing),
+            (consumed < input.len()) as usize
+        );
+    }
+
+    assert_eq!(
+        Duration::parse_prefix(b"839:00:00abc", 0).unwrap_err(),
+        ParseError::HourOutOfRange(0)
+    );
+    assert_eq!(
+        Duration::parse_prefix(b"abc", 0).unwrap_err(),
+        ParseError::InvalidCharacter(0)
+    );
+}
+
+#[test]
+fn std_duration() {
+    let duration = Duration::parse(b"-1 2:3:4.567891", 6).unwrap();
+    let (std, neg) = duration.to_std();
+    assert_eq!((std, neg), (StdDuration::new(93784, 567_891_000), true));
+    assert_eq!(Duration::try_from((std, neg)).unwrap().0, duration.0);
+    assert_eq!(duration.to_nanos(), -93_784_567_891_000);
+    assert_eq!(
+        Duration::from_nanos(-93_784_567_891_000, 6).unwrap().0,
+        duration.0
+    );
+    assert_eq!(duration.to_micros(), -93_784_567_891);
+    assert_eq!(
+        Duration::from_micros(-93_784_567_891, 6).unwrap().0,
+        duration.0
+    );
+    assert_eq!(
+        Duration::from_micros(839 * 3600 * 1_000_000, 6).unwrap_err(),
+        ParseError::HourOutOfRange(0)
+    );
+
+    let rounded = Duration::from_std(StdDuration::new(59, 999_999_999), false, 6).unwrap();
+    assert_eq!(rounded.to_string(), "00:01:00.000000");
+    assert_eq!(
+        Duration::from_nanos(-400, 6).unwrap().to_string(),
+        "00:00:00.000000"
+    );
+    assert_eq!(
+        Duration::try_from((StdDuration::from_secs(839 * 3600), false)).unwrap_err(),
+        ParseError::HourOutOfRange(0)
+    );
+}
+
+#[test]
+fn to_number() {
+    let cases = vec![
+        ("12:34:56.78", 2, 123457, 123456.78, "123456.78"),
+        ("-12:34:56.4", 1, -123456, -123456.4, "-123456.4"),
+        ("12:59:59.5", 0, 130000, 130000.0, "130000"),
+        ("12:59:59.5", 1, 130000, 125959.5, "125959.5"),
+        ("838:59:59", 6, 8385959, 8385959.0, "8385959.000000"),
+        ("0:0:1.05", 2, 1, 1.05, "1.05"),
+        ("-0:0:0.4", 0, 0, 0.0, "0"),
+    ];
+    for (input, fsp, int, float, decimal) in cases {
+        let duration = Duration::parse(input.as_bytes(), fsp).unwrap();
+        assert_eq!(duration.to_i64_hhmmss(), int, "{}", input);
+        assert!((duration.to_f64() - float).abs() < 1e-9, "{}", input);
+        assert_eq!(duration.to_decimal_string(), decimal, "{}", input);
+    }
+}
+
+#[test]
+fn negative_zero() {
+    for &(input, fsp) in &[("-0:0:0.4", 0), ("-00:00:00", 0), ("-0 0:0:0.000", 3)] {
+        let duration = Duration::parse(input.as_bytes(), fsp).unwrap();
+        assert!(!duration.neg(), "{}", input);
+        assert!(duration.to_f64().is_sign_positive(), "{}", input);
+        assert_eq!(
+            duration.to_std(),
+            (StdDuration::new(0, 0), false),
+            "{}",
+            input
+        );
+        assert_eq!(duration, Duration::zero(), "{}", input);
+    }
+}
+
+#[test]
+fn bits() {
+    let duration = Duration::parse(b"-1 2:3:4.567891", 6).unwrap();
+    assert_eq!(
+        Duration::from_bits(duration.to_bits()).unwrap().0,
+        duration.0
+    );
+    let micro_bits = duration.to_micro_bits().unwrap();
+    assert_eq!(Duration::from_micro_bits(micro_bits).unwrap().0, duration.0);
+    assert_eq!(
+        TimeFields::from_micro_bits(micro_bits).unwrap(),
+        TimeFields {
+            neg: true,
+            hour: 26,
+            minute: 3,
+            second: 4,
+            nanos: 567_891_000,
+            fsp: 6,
+        }
+    );
+    // Minute 60.
+    assert_eq!(
+        Duration::from_bits(60 << 47).unwrap_err(),
+        ParseError::MinuteOutOfRange(0)
+    );
+    // One nanosecond at fsp 0.
+    assert_eq!(
+        Duration::from_bits(1 << 9).unwrap_err(),
+        ParseError::Overflow(0)
+    );
+}
+
+#[test]
+fn from_parts() {
+    let duration = Duration::from_parts(true, 26, 3, 4, 567_891, 6).unwrap();
+    assert_eq!(
+        duration.0,
+        Duration::parse(b"-1 2:3:4.567891", 6).unwrap().0
+    );
+    assert_eq!(
+        (
+            duration.neg(),
+            duration.hour(),
+            duration.minute(),
+            duration.second()
+        ),
+        (true, 26, 3, 4)
+    );
+    assert_eq!(duration.nano(), 567_891_000);
+    assert_eq!(
+        Duration::from_parts(false, 1, 2, 3, 999_999, 5)
+            .unwrap()
+            .to_string(),
+        "01:02:04.00000"
+    );
+
+    let errors = vec![
+        ((false, 839, 0, 0, 0, 0), ParseError::HourOutOfRange(0)),
+        ((false, 838, 59, 59, 1, 6), ParseError::HourOutOfRange(0)),
+        ((false, 1, 60, 0, 0, 0), ParseError::MinuteOutOfRange(0)),
+        ((false, 1, 0, 60, 0, 0), ParseError::SecondOutOfRange(0)),
+        ((false, 1, 0, 0, 1_000_000, 0), ParseError::Overflow(0)),
+        ((false, 1, 0, 0, 0, 7), ParseError::InvalidFsp(7)),
+    ];
+    for ((neg, hour, minute, second, micros, fsp), err) in errors {
+        assert_eq!(
+            Duration::from_parts(neg, hour, minute, second, micros, fsp).unwrap_err(),
+            err
+        );
+    }
+}
+
+#[test]
+fn ord_eq_hash() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let hash = |d: &Duration| {
+        let mut hasher = DefaultHasher::new();
+        d.hash(&mut hasher);
+        hasher.finish()
+    };
+    // xorshift64*, enough to spread values over the whole range without a dependency.
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut random = |n: u64| {
+        seed ^= seed >> 12;
+        seed ^= seed << 25;
+        seed ^= seed >> 27;
+        seed.wrapping_mul(0x2545_f491_4f6c_dd1d) % n
+    };
+
+    let mut values = Vec::new();
+    for _ in 0..1000 {
+        let mut micros = random(838 * 3600 * 1_000_000) as i64;
+        if random(2) == 0 {
+            micros = -micros;
+        }
+        // Whole seconds are the same value with every fsp.
+        let mut fsp = 6;
+        if random(4) == 0 {
+            micros = micros / 1_000_000 * 1_000_000;
+            fsp = random(7) as i8;
+        }
+        let (a, b) = (
+            Duration::from_micros(micros, 6).unwrap(),
+            Duration::from_micros(micros, fsp).unwrap(),
+        );
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        values.push((micros, a));
+    }
+    for pair in values.windows(2) {
+        let ((x, a), (y, b)) = (pair[0], pair[1]);
+        assert_eq!(a.cmp(&b), x.cmp(&y), "{} {}", a, b);
+        assert_eq!(a == b, x == y);
+    }
+    values.sort_by_key(|&(_, d)| d);
+    assert!(values.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+    assert_eq!(
+        Duration::parse(b"-0:0:0.0", 1).unwrap(),
+        Duration::parse(b"0", 0).unwrap()
+    );
+    assert_eq!(
+        Duration::parse(b"12:00:00.0", 1).unwrap(),
+        Duration::parse(b"12:00:00", 0).unwrap()
+    );
+    assert!(Duration::parse(b"-1", 0).unwrap() < Duration::parse(b"1", 0).unwrap());
+    assert!(Duration::parse(b"-2", 0).unwrap() < Duration::parse(b"-1", 0).unwrap());
+}
+
+#[test]
+fn display() {
+    let cases = vec![
+        ("12:34:56", 0, "12:34:56"),
+        ("-1 1:2:3.123567", 6, "-25:02:03.123567"),
+        ("1:2:3.1235", 3, "01:02:03.124"),
+        ("838:59:59", 2, "838:59:59.00"),
+        ("-0:0:0.4", 0, "00:00:00"),
+        ("2019-05-15 01:02:03", 1, "01:02:03.0"),
+        ("  .5", 1, "00:00:00.5"),
+    ];
+    for (input, fsp, expected) in cases {
+        let duration = Duration::parse(input.as_bytes(), fsp).unwrap();
+        assert_eq!(duration.to_string(), expected, "{}", input);
+        let mut buf = b"t=".to_vec();
+        duration.write_to(&mut buf);
+        assert_eq!(&buf[2..], expected.as_bytes());
+    }
+}
+
+#[test]
+fn duration_parser() {
+    let cases = vec![
+        "  -12:34:56.789  ",
+        "1 23:59:59.9999999",
+        "2019-05-15 12:34:56.5",
+        "20190515123456",
+        "838:59:59.5",
+        "12:34:",
+        "12:3a:56",
+        "99999999",
+    ];
+    for input in cases {
+        let expected = Duration::parse(input.as_bytes(), 6).map(|d| d.0);
+        for split in 0..=input.len() {
+            let (head, tail) = input.as_bytes().split_at(split);
+            let mut parser = DurationParser::new(6).unwrap();
+            let actual = parser
+                .feed(head)
+                .and_then(|_| parser.feed(tail))
+                .and_then(|_| parser.finish())
+                .map(|d| d.0);
+            assert_eq!(actual, expected, "{} split at {}", input, split);
+        }
+    }
+}
+
+#[test]
+fn from_f64_and_decimal_str() {
+    let cases = vec![
+        (123456.789, "123456.789", 2, "12:34:56.790000000"),
+        (-123456.785, "-1.23456785e5", 2, "-12:34:56.790000000"),
+        (235959.5, "235959.5", 0, "24:00:00.0"),
+        (0.0000005, "5e-7", 6, "0:00:00.1000"),
+        (-0.4, "-0.4", 0, "0:00:00.0"),
+        (
+            20190515123456.5,
+            "20190515123456.5",
+            1,
+            "12:34:56.500000000",
+        ),
+    ];
+    for (float, decimal, fsp, expected) in cases {
+        for duration in [
+            Duration::from_f64(float, fsp).unwrap(),
+            Duration::from_decimal_str(decimal, fsp).unwrap(),
+        ] {
+            let actual = format!(
+                "{}{}:{:02}:{:02}.{}",
+                if duration.neg() { "-" } else { "" },
+                duration.hour(),
+                duration.minute(),
+                duration.second(),
+                duration.nano()
+            );
+            assert_eq!(actual, expected, "{}", decimal);
+        }
+    }
+
+    assert_eq!(
+        Duration::from_f64(8385959.5, 0).unwrap_err(),
+        ParseError::HourOutOfRange(0)
+    );
+    assert_eq!(
+        Duration::from_f64(f64::NAN, 0).unwrap_err(),
+        ParseError::Overflow(0)
+    );
+    assert_eq!(
+        Duration::from_decimal_str("1e20", 0).unwrap_err(),
+        ParseError::Overflow(0)
+    );
+    assert_eq!(
+        Duration::from_decimal_str("12:34", 0).unwrap_err(),
+        ParseError::InvalidCharacter(2)
+    );
+}
+
 #[bench]
 fn bench(b: &mut test::Bencher) {
     b.iter(|| {
         let _ = test::black_box(Duration::parse(test::black_box(b"-1 1:2:3.123567"), 6));
     })
 }
+
+#[bench]
+fn bench_canonical(b: &mut test::Bencher) {
+    b.iter(|| {
+        let _ = test::black_box(Duration::parse(test::black_box(b"12:34:56.123567"), 6));
+    })
+}
+
+#[bench]
+fn bench_canonical_state_machine(b: &mut test::Bencher) {
+    b.iter(|| {
+        let mut parser = DurationParser::new(6).unwrap();
+        parser.feed(test::black_box(b"12:34:56.123567")).unwrap();
+        let _ = test::black_box(parser.finish());
+    })
+}
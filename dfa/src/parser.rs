@@ -0,0 +1,433 @@
+use duration_common::{check_date, check_hour_with_mode, split_hhmmss};
+
+use crate::{
+    check_fsp, check_minute, check_second, Duration, DurationBuilder, EvalContext, ParseError,
+    ParseMode, Result, Warning, NANO_WIDTH, TEN_POW,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    Start,
+    Block,
+    PostBlock,
+    MonthDash,
+    Month,
+    DayDash,
+    MonthDay,
+    PostDate,
+    Hour,
+    MinuteColon,
+    Minute,
+    SecondColon,
+    Second,
+    Dot,
+    Fraction,
+    Consume,
+    End,
+}
+
+#[inline]
+fn to_dec(d: u8) -> u64 {
+    u64::from(d - b'0')
+}
+
+/// The state machine behind `Duration::parse`, for input that does not arrive in one piece.
+///
+/// Push the input with `feed` as it comes in and get the value with `finish`; the result is the
+/// same as parsing the concatenated input at once, error offsets included. Once `feed` failed,
+/// the parser must not be used any more.
+#[derive(Clone, Debug)]
+pub struct DurationParser {
+    fsp: u8,
+    mode: ParseMode,
+    state: State,
+    // The offset of the next byte fed.
+    pos: usize,
+    neg: bool,
+    block: u64,
+    day: u64,
+    hour: u64,
+    minute: u64,
+    second: u64,
+    fract: u64,
+    eaten: u8,
+    block_len: usize,
+    // The start of the first block, which is the most significant field of the value.
+    block_start: usize,
+    has_date: bool,
+    year: u64,
+    month: u64,
+    month_day: u64,
+    date_start: usize,
+    hour_start: usize,
+    // The start of the minute or second being read.
+    field_start: usize,
+    digits: usize,
+    // Whether a non-zero digit beyond `fsp` was rounded away.
+    truncated: bool,
+}
+
+impl DurationParser {
+    pub fn new(fsp: i8) -> Result<Self> {
+        DurationParser::with_mode(fsp, ParseMode::Strict)
+    }
+
+    pub fn with_mode(fsp: i8, mode: ParseMode) -> Result<Self> {
+        Ok(DurationParser {
+            fsp: check_fsp(fsp)?,
+            mode,
+            state: State::Start,
+            pos: 0,
+            neg: false,
+            block: 0,
+            day: 0,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            fract: 0,
+            eaten: 0,
+            block_len: 0,
+            block_start: 0,
+            has_date: false,
+            year: 0,
+            month: 0,
+            month_day: 0,
+            date_start: 0,
+            hour_start: 0,
+            field_start: 0,
+            digits: 0,
+            truncated: false,
+        })
+    }
+
+    // The accumulators are capped rather than rejected, `Duration::build` clamps the value.
+    #[inline]
+    fn saturate(&self) -> bool {
+        self.mode == ParseMode::Truncate
+    }
+
+    #[inline]
+    fn check_hour(&self, hour: u64, offset: usize) -> Result<u64> {
+        check_hour_with_mode(hour, self.mode, offset)
+    }
+
+    /// Push the next piece of the input.
+    pub fn feed(&mut self, input: &[u8]) -> Result<()> {
+        for &c in input {
+            let i = self.pos;
+            self.pos += 1;
+            self.state = self.step(c, i)?;
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, c: u8, i: usize) -> Result<State> {
+        use State::*;
+
+        let fsp = self.fsp;
+        if c == b'.'
+            && (self.state == Start
+                || self.state == Block
+                || self.state == PostBlock
+                || self.state == Hour
+                || self.state == Minute
+                || self.state == Second)
+        {
+            return Ok(Dot);
+        }
+        Ok(match self.state {
+            Start => {
+                if c.is_ascii_digit() {
+                    self.block = to_dec(c);
+                    self.block_len = 1;
+                    self.block_start = i;
+                    Block
+                } else if c.is_ascii_whitespace() {
+                    Start
+                } else if c == b'-' {
+                    if self.neg {
+                        return Err(ParseError::InvalidCharacter(i));
+                    } else {
+                        self.neg = true;
+                        Start
+                    }
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Block => {
+                if c.is_ascii_digit() {
+                    self.block_len += 1;
+                    if self.block_len <= 14 {
+                        self.block = self.block * 10 + to_dec(c);
+                    } else if !self.saturate() {
+                        // Too long for a `YYYYMMDDhhmmss` datetime, so it can only be `hhmmss`
+                        // with far too many hours.
+                        return Err(ParseError::HourOutOfRange(self.block_start));
+                    }
+                    Block
+                } else if c.is_ascii_whitespace() {
+                    PostBlock
+                } else if c == b':' {
+                    self.hour = self.check_hour(self.block, self.block_start)?;
+                    self.hour_start = self.block_start;
+                    self.block = 0;
+                    self.block_len = 0;
+                    MinuteColon
+                } else if c == b'-' && self.block_len == 4 && !self.neg {
+                    self.has_date = true;
+                    self.date_start = self.block_start;
+                    self.year = self.block;
+                    self.block = 0;
+                    self.block_len = 0;
+                    MonthDash
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            PostBlock => {
+                if c.is_ascii_digit() {
+                    self.hour = to_dec(c);
+                    self.hour_start = i;
+                    self.day = self.block;
+                    self.block = 0;
+                    self.block_len = 0;
+                    Hour
+                } else if c.is_ascii_whitespace() {
+                    PostBlock
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            MonthDash => {
+                if c.is_ascii_digit() {
+                    self.month = to_dec(c);
+                    self.digits = 1;
+                    Month
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Month => {
+                if c.is_ascii_digit() && self.digits < 2 {
+                    self.month = self.month * 10 + to_dec(c);
+                    self.digits += 1;
+                    Month
+                } else if c == b'-' {
+                    DayDash
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            DayDash => {
+                if c.is_ascii_digit() {
+                    self.month_day = to_dec(c);
+                    self.digits = 1;
+                    MonthDay
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            MonthDay => {
+                if c.is_ascii_digit() && self.digits < 2 {
+                    self.month_day = self.month_day * 10 + to_dec(c);
+                    self.digits += 1;
+                    MonthDay
+                } else if c.is_ascii_whitespace() || c == b'T' {
+                    PostDate
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            PostDate => {
+                if c.is_ascii_digit() {
+                    self.hour = to_dec(c);
+                    self.hour_start = i;
+                    Hour
+                } else if c.is_ascii_whitespace() {
+                    PostDate
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Hour => {
+                if c.is_ascii_digit() {
+                    self.hour = self.check_hour(self.hour * 10 + to_dec(c), self.hour_start)?;
+                    Hour
+                } else if c.is_ascii_whitespace() {
+                    End
+                } else if c == b':' {
+                    MinuteColon
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            MinuteColon => {
+                if c.is_ascii_digit() {
+                    self.minute = to_dec(c);
+                    self.field_start = i;
+                    Minute
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Minute => {
+                if c.is_ascii_digit() {
+                    self.minute = check_minute(self.minute * 10 + to_dec(c))
+                        .map_err(|e| e.at(self.field_start))?;
+                    Minute
+                } else if c.is_ascii_whitespace() {
+                    End
+                } else if c == b':' {
+                    SecondColon
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            SecondColon => {
+                if c.is_ascii_digit() {
+                    self.second = to_dec(c);
+                    self.field_start = i;
+                    Second
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Second => {
+                if c.is_ascii_digit() {
+                    self.second = check_second(self.second * 10 + to_dec(c))
+                        .map_err(|e| e.at(self.field_start))?;
+                    Second
+                } else if c.is_ascii_whitespace() {
+                    End
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Dot => {
+                if c.is_ascii_digit() {
+                    if fsp == 0 {
+                        if to_dec(c) > 4 {
+                            self.fract = 1;
+                        }
+                        self.truncated |= c != b'0';
+                        Consume
+                    } else {
+                        self.fract = to_dec(c);
+                        self.eaten = 1;
+                        Fraction
+                    }
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Fraction => {
+                if c.is_ascii_digit() {
+                    if self.eaten < fsp {
+                        self.fract = self.fract * 10 + to_dec(c);
+                        self.eaten += 1;
+                        Fraction
+                    } else {
+                        if to_dec(c) > 4 {
+                            self.fract += 1;
+                        }
+                        self.truncated |= c != b'0';
+                        Consume
+                    }
+                } else if c.is_ascii_whitespace() {
+                    End
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            Consume => {
+                if c.is_ascii_digit() {
+                    self.truncated |= c != b'0';
+                    Consume
+                } else if c.is_ascii_whitespace() {
+                    End
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+            End => {
+                if c.is_ascii_whitespace() {
+                    End
+                } else {
+                    return Err(ParseError::InvalidCharacter(i));
+                }
+            }
+        })
+    }
+
+    /// Finish parsing once all the input was fed.
+    pub fn finish(self) -> Result<Duration> {
+        self.finish_with_ctx(&mut EvalContext::default())
+    }
+
+    /// Finish parsing like `finish`, recording the warnings raised on the way into `ctx`.
+    /// Nothing is recorded if parsing fails. The `ParseMode` of the parser is used, not the one
+    /// of `ctx`.
+    pub fn finish_with_ctx(mut self, ctx: &mut EvalContext) -> Result<Duration> {
+        use State::*;
+
+        let len = self.pos;
+        match self.state {
+            Start if !self.neg => return Err(ParseError::EmptyInput),
+            MinuteColon | SecondColon => return Err(ParseError::DanglingColon(len - 1)),
+            MonthDash | Month | DayDash | MonthDay | PostDate => {
+                return Err(ParseError::InvalidCharacter(len))
+            }
+            _ => {}
+        }
+        if self.block_len == 14 {
+            self.has_date = true;
+            self.date_start = self.block_start;
+            self.hour_start = self.block_start + 8;
+            self.year = self.block / 10_000_000_000;
+            self.month = self.block / 100_000_000 % 100;
+            self.month_day = self.block / 1_000_000 % 100;
+            self.block %= 1_000_000;
+        }
+        if self.has_date {
+            if self.neg {
+                return Err(ParseError::InvalidDate(self.date_start));
+            }
+            check_date(self.year, self.month, self.month_day).map_err(|e| e.at(self.date_start))?;
+        }
+        let (mut hour, minute, second) = if self.block != 0 {
+            // Only the first 14 digits were accumulated.
+            let len = self.block_len.min(14);
+            split_hhmmss(self.block, self.mode, self.block_start, len)?
+        } else {
+            (self.hour, self.minute, self.second)
+        };
+        if self.has_date && hour > 23 {
+            return Err(ParseError::HourOutOfRange(self.hour_start));
+        }
+        hour += self.day * 24;
+        let nano = self.fract * u64::from(TEN_POW[NANO_WIDTH as usize - self.eaten as usize]);
+        let (duration, clamped) = Duration::build(DurationBuilder {
+            neg: self.neg,
+            hour,
+            minute,
+            second,
+            nano,
+            fsp: self.fsp,
+            round_with_fsp: false,
+            mode: self.mode,
+        })
+        .map_err(|e| e.at(self.block_start))?;
+        if self.truncated {
+            ctx.warn(Warning::TruncatedFraction);
+        }
+        if self.has_date && self.year == 0 && self.month == 0 && self.month_day == 0 {
+            ctx.warn(Warning::ZeroDate);
+        }
+        if clamped {
+            ctx.warn(Warning::ClampedRange);
+        }
+        // `-00:00:00`, or a fraction rounded away, is zero without a sign.
+        Ok(duration.clear_neg_if_zero())
+    }
+}
@@ -0,0 +1,99 @@
+use std::convert::{TryFrom, TryInto};
+
+use crate::{check_fsp, DatumError, Duration};
+
+impl Duration {
+    /// Append the value as a column value of TiDB's row format v2: the signed nanoseconds as a
+    /// little-endian integer of the smallest of 1, 2, 4 or 8 bytes that holds them. The width is
+    /// not encoded, the row stores the offset at which each column value ends.
+    pub fn encode_row_v2(&self, buf: &mut Vec<u8>) {
+        let nanos = self.to_nanos();
+        if let Ok(nanos) = i8::try_from(nanos) {
+            buf.extend_from_slice(&nanos.to_le_bytes());
+        } else if let Ok(nanos) = i16::try_from(nanos) {
+            buf.extend_from_slice(&nanos.to_le_bytes());
+        } else if let Ok(nanos) = i32::try_from(nanos) {
+            buf.extend_from_slice(&nanos.to_le_bytes());
+        } else {
+            buf.extend_from_slice(&nanos.to_le_bytes());
+        }
+    }
+
+    /// Decode a row format v2 column value, which is the whole of `data`, rounding it to the
+    /// column `fsp`.
+    pub fn decode_row_v2(data: &[u8], fsp: i8) -> Result<Duration, DatumError> {
+        check_fsp(fsp).map_err(|_| DatumError::InvalidFsp(fsp))?;
+        let nanos = match data.len() {
+            1 => i64::from(data[0] as i8),
+            2 => i64::from(i16::from_le_bytes(data.try_into().unwrap())),
+            4 => i64::from(i32::from_le_bytes(data.try_into().unwrap())),
+            8 => i64::from_le_bytes(data.try_into().unwrap()),
+            len => return Err(DatumError::InvalidLength(len)),
+        };
+        Duration::from_stored_nanos(nanos, fsp)
+    }
+}
+
+#[test]
+fn row_v2() {
+    use crate::{d, encoded};
+
+    let encode = |d: &Duration| encoded(Duration::encode_row_v2, d);
+
+    let cases = vec![
+        ("0", 0, 1),
+        ("0:0:0.000001", 6, 2),
+        ("-0:0:0.000032", 6, 2),
+        ("0:0:0.000033", 6, 4),
+        ("0:0:2.147483", 6, 4),
+        ("-0:0:2.147483", 6, 4),
+        ("0:0:2.147484", 6, 8),
+        ("838:59:59", 0, 8),
+        ("-838:59:59", 6, 8),
+    ];
+    for (input, fsp, width) in cases {
+        let value = d(input, fsp);
+        let buf = encode(&value);
+        assert_eq!(buf.len(), width, "{}", input);
+        let decoded = Duration::decode_row_v2(&buf, fsp).unwrap();
+        assert_eq!(decoded.to_string(), value.to_string());
+    }
+
+    // Values of every width written by another encoder, rounded to the column fsp.
+    let raw: Vec<(&[u8], i8, &str)> = vec![
+        (&[0x7f], 6, "00:00:00.000000"),
+        (&[0xe8, 0x03], 6, "00:00:00.000001"),
+        (&[0x18, 0xfc], 6, "-00:00:00.000001"),
+        (&[0x80, 0xf0, 0xfa, 0x02], 1, "00:00:00.1"),
+        (
+            &[0x00, 0x36, 0x65, 0xc4, 0xff, 0xff, 0xff, 0xff],
+            0,
+            "-00:00:01",
+        ),
+    ];
+    for (data, fsp, expected) in raw {
+        let decoded = Duration::decode_row_v2(data, fsp).unwrap();
+        assert_eq!(decoded.to_string(), expected);
+    }
+
+    // `838:59:59.999999` is past the TIME range, either way.
+    let max = 3_020_399_999_999_000i64;
+    for nanos in [max, -max] {
+        assert_eq!(
+            Duration::decode_row_v2(&nanos.to_le_bytes(), 6).unwrap_err(),
+            DatumError::OutOfRange(nanos)
+        );
+    }
+    assert_eq!(
+        Duration::decode_row_v2(&[0, 0, 0], 0).unwrap_err(),
+        DatumError::InvalidLength(3)
+    );
+    assert_eq!(
+        Duration::decode_row_v2(&[], 0).unwrap_err(),
+        DatumError::InvalidLength(0)
+    );
+    assert_eq!(
+        Duration::decode_row_v2(&[0], -2).unwrap_err(),
+        DatumError::InvalidFsp(-2)
+    );
+}
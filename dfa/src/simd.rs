@@ -0,0 +1,184 @@
+//! A fast path for the canonical `hh:mm:ss[.ffffff]` shape, which most stored TIME values are
+//! in. Anything else, including canonical values that would need rounding or fail to parse, is
+//! left to the state machine, so the fast path never changes a result.
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use crate::{Duration, DurationBuilder, ParseMode, MAX_MINUTES, MAX_SECONDS};
+
+/// The shape of a canonical value, also the padding of shorter ones: a missing fraction reads as
+/// `.000000`, and the last byte only rounds the length up to 16.
+const TEMPLATE: &[u8; 16] = b"00:00:00.0000000";
+/// The positions of the separators in `TEMPLATE`, everything else is a digit.
+const SEPARATORS: u32 = 1 << 2 | 1 << 5 | 1 << 8;
+const HHMMSS_LEN: usize = 8;
+
+/// Parse `s` if it is exactly `hh:mm:ss` or `hh:mm:ss.f` with at most `fsp` fraction digits.
+#[inline]
+pub(crate) fn parse_canonical(s: &[u8], fsp: u8) -> Option<Duration> {
+    if s.len() != HHMMSS_LEN && !(HHMMSS_LEN + 2..=HHMMSS_LEN + 1 + fsp as usize).contains(&s.len())
+    {
+        return None;
+    }
+    let mut buf = *TEMPLATE;
+    buf[..s.len()].copy_from_slice(s);
+    let [hour, minute, second, micro] = decode(&buf)?;
+    if minute > MAX_MINUTES || second > MAX_SECONDS {
+        return None;
+    }
+    Duration::build(DurationBuilder {
+        neg: false,
+        hour,
+        minute,
+        second,
+        nano: micro * 1000,
+        fsp,
+        round_with_fsp: false,
+        mode: ParseMode::Strict,
+    })
+    .ok()
+    .map(|(duration, _)| duration)
+}
+
+/// Validate a padded canonical value and decode it into hours, minutes, seconds and micros.
+#[inline]
+fn decode(buf: &[u8; 16]) -> Option<[u64; 4]> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("sse2") {
+            return unsafe { decode_sse2(buf) };
+        }
+    }
+    decode_scalar(buf)
+}
+
+fn decode_scalar(buf: &[u8; 16]) -> Option<[u64; 4]> {
+    for (i, (&c, &t)) in buf.iter().zip(TEMPLATE.iter()).enumerate() {
+        let valid = if SEPARATORS >> i & 1 == 1 {
+            c == t
+        } else {
+            c.is_ascii_digit()
+        };
+        if !valid {
+            return None;
+        }
+    }
+    let pair = |i: usize| u64::from(buf[i] - b'0') * 10 + u64::from(buf[i + 1] - b'0');
+    Some([
+        pair(0),
+        pair(3),
+        pair(6),
+        pair(9) * 10000 + pair(11) * 100 + pair(13),
+    ])
+}
+
+/// Validate all 16 bytes at once, then combine every byte with the next one into the number of
+/// the pair starting there, of which only the pairs at the fields are read.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn decode_sse2(buf: &[u8; 16]) -> Option<[u64; 4]> {
+    let input = _mm_loadu_si128(buf.as_ptr() as *const __m128i);
+    let template = _mm_loadu_si128(TEMPLATE.as_ptr() as *const __m128i);
+    let nine = _mm_set1_epi8(9);
+
+    // Digits are the bytes that are at most 9 after subtracting '0' as unsigned.
+    let values = _mm_sub_epi8(input, _mm_set1_epi8(b'0' as i8));
+    let digits = _mm_movemask_epi8(_mm_cmpeq_epi8(_mm_max_epu8(values, nine), nine)) as u32;
+    let separators = _mm_movemask_epi8(_mm_cmpeq_epi8(input, template)) as u32;
+    if digits | SEPARATORS != 0xffff || separators & SEPARATORS != SEPARATORS {
+        return None;
+    }
+
+    // There is no byte multiply, so the tens are `8 * x + 2 * x`. At most 99, nothing carries
+    // into the next byte.
+    let twice = _mm_add_epi8(values, values);
+    let eight = _mm_add_epi8(_mm_add_epi8(twice, twice), _mm_add_epi8(twice, twice));
+    let pairs = _mm_add_epi8(_mm_add_epi8(eight, twice), _mm_srli_si128(values, 1));
+    let mut out = [0u8; 16];
+    _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, pairs);
+    let pair = |i: usize| u64::from(out[i]);
+    Some([
+        pair(0),
+        pair(3),
+        pair(6),
+        pair(9) * 10000 + pair(11) * 100 + pair(13),
+    ])
+}
+
+#[test]
+fn canonical() {
+    // Inputs the fast path takes from the given fsp on, with fewer fraction digits they need
+    // rounding.
+    let canonical = [
+        ("12:34:56", 0),
+        ("00:00:00.0", 1),
+        ("23:59:59.999999", 6),
+        ("99:59:59.5", 1),
+        ("12:34:56.123", 3),
+    ];
+    let others = [
+        "12:34:56.1234567",
+        "12:34:56.",
+        "12:60:00",
+        "12:34:60.1",
+        "12:34:5x",
+        "12-34-56",
+        " 2:34:56",
+        "12:34:56.12 ",
+        "-12:34:56",
+        "1:2:3",
+    ];
+    let state_machine = |input: &str, fsp: u8| {
+        let mut parser = crate::DurationParser::new(fsp as i8).unwrap();
+        parser
+            .feed(input.as_bytes())
+            .and_then(|_| parser.finish())
+            .map(|d| d.0)
+    };
+    let inputs = || canonical.iter().map(|&(input, _)| input).chain(others);
+    for fsp in 0..=6 {
+        for &(input, min_fsp) in &canonical {
+            let expected = state_machine(input, fsp).ok().filter(|_| fsp >= min_fsp);
+            let actual = parse_canonical(input.as_bytes(), fsp).map(|d| d.0);
+            assert_eq!(actual, expected, "{} with fsp {}", input, fsp);
+        }
+        for input in &others {
+            assert!(
+                parse_canonical(input.as_bytes(), fsp).is_none(),
+                "{}",
+                input
+            );
+        }
+        for input in inputs() {
+            let actual = Duration::parse(input.as_bytes(), fsp as i8).map(|d| d.0);
+            assert_eq!(
+                actual,
+                state_machine(input, fsp),
+                "{} with fsp {}",
+                input,
+                fsp
+            );
+        }
+    }
+
+    for input in inputs() {
+        let mut buf = *TEMPLATE;
+        let len = input.len().min(16);
+        buf[..len].copy_from_slice(&input.as_bytes()[..len]);
+        // SSE2 is part of x86_64, so the vector path is always taken there.
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert!(is_x86_feature_detected!("sse2"));
+            assert_eq!(
+                unsafe { decode_sse2(&buf) },
+                decode_scalar(&buf),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(decode(&buf), decode_scalar(&buf), "{}", input);
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::Write;
+
+use crate::Duration;
+
+impl Duration {
+    /// Render the value like MySQL's `TIME_FORMAT(time, format)`.
+    ///
+    /// The hour, minute, second and microsecond specifiers are supported: `%H %k %h %I %l %i %S
+    /// %s %f %p %r %T %%`. `%H` and `%k` print hours above 24 as they are, the 12-hour specifiers
+    /// use the hour of the day. Like MySQL, the date parts `%Y %y %m %c %d %e` print as zero and
+    /// the other date specifiers (`%M %b %W %a %D %j %U %u %V %v %X %x %w`) make the result NULL,
+    /// which is `None` here. Any other character following a `%` is copied, and a negative value
+    /// is prefixed with `-` unless it is zero, as in `TimeStr`.
+    pub fn time_format(&self, format: &str) -> Option<String> {
+        let mut buf = Vec::with_capacity(format.len() + 8);
+        self.write_time_format(format.as_bytes(), &mut buf)?;
+        Some(unsafe { String::from_utf8_unchecked(buf) })
+    }
+
+    /// Append the value rendered like `time_format` to `buf`. On `None`, `buf` is left as it was.
+    pub fn write_time_format(&self, format: &[u8], buf: &mut Vec<u8>) -> Option<()> {
+        let (hour, minute, second) = (self.hour(), self.minute(), self.second());
+        let hour12 = (hour % 24 + 11) % 12 + 1;
+        let am_pm = if hour % 24 < 12 { "AM" } else { "PM" };
+
+        let start = buf.len();
+        if self.neg() && self.to_nanos() != 0 {
+            buf.push(b'-');
+        }
+        let mut chars = format.iter();
+        while let Some(&c) = chars.next() {
+            let spec = match chars.as_slice().first() {
+                Some(&spec) if c == b'%' => spec,
+                _ => {
+                    buf.push(c);
+                    continue;
+                }
+            };
+            chars.next();
+            // Writing to a `Vec` cannot fail.
+            let _ = match spec {
+                b'H' => write!(buf, "{:02}", hour),
+                b'k' => write!(buf, "{}", hour),
+                b'h' | b'I' => write!(buf, "{:02}", hour12),
+                b'l' => write!(buf, "{}", hour12),
+                b'i' => write!(buf, "{:02}", minute),
+                b'S' | b's' => write!(buf, "{:02}", second),
+                b'f' => write!(buf, "{:06}", self.nano() / 1000),
+                b'p' => write!(buf, "{}", am_pm),
+                b'r' => write!(buf, "{:02}:{:02}:{:02} {}", hour12, minute, second, am_pm),
+                b'T' => write!(buf, "{:02}:{:02}:{:02}", hour, minute, second),
+                b'Y' => write!(buf, "0000"),
+                b'y' | b'm' | b'd' => write!(buf, "00"),
+                b'c' | b'e' => write!(buf, "0"),
+                b'M' | b'b' | b'W' | b'a' | b'D' | b'j' | b'U' | b'u' | b'V' | b'v' | b'X'
+                | b'x' | b'w' => {
+                    buf.truncate(start);
+                    return None;
+                }
+                _ => {
+                    buf.push(spec);
+                    Ok(())
+                }
+            };
+        }
+        Some(())
+    }
+}
+
+#[test]
+fn time_format() {
+    let cases = vec![
+        (
+            "12:34:56.789",
+            "%H %k %h %I %l %i %S %s %f %p",
+            "12 12 12 12 12 34 56 56 789000 PM",
+        ),
+        ("100:02:03", "%H|%k|%h|%l|%p", "100|100|04|4|AM"),
+        ("0:0:0", "%h %l %p %r", "12 12 AM 12:00:00 AM"),
+        ("23:59:59", "%r", "11:59:59 PM"),
+        ("-1:02:03.5", "%T.%f", "-01:02:03.500000"),
+        ("838:59:59", "%T %%", "838:59:59 %"),
+        (
+            "1:2:3",
+            "%Y-%m-%d %y %c %e at 100%",
+            "0000-00-00 00 0 0 at 100%",
+        ),
+        ("1:2:3", "%é%H", "é01"),
+        ("-0:0:0.0000001", "%T.%f", "00:00:00.000000"),
+    ];
+    for (input, format, expected) in cases {
+        let duration = Duration::parse(input.as_bytes(), 6).unwrap();
+        assert_eq!(
+            duration.time_format(format).as_deref(),
+            Some(expected),
+            "{} {}",
+            input,
+            format
+        );
+    }
+
+    let duration = Duration::parse(b"1:2:3", 0).unwrap();
+    for format in ["%x %Y", "%H %M", "%D", "%j", "%W %a %b %U %u %V %v %X %w"] {
+        assert_eq!(duration.time_format(format), None, "{}", format);
+    }
+    let mut buf = b"abc".to_vec();
+    assert_eq!(duration.write_time_format(b"%H %D", &mut buf), None);
+    assert_eq!(buf, b"abc");
+}
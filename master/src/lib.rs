@@ -3,6 +3,10 @@ extern crate test;
 use std::str;
 use time::{self, Tm};
 
+use duration_common::{check_hour_with_mode, split_day, split_hhmmss, trim_input};
+
+pub use duration_common::{EvalContext, ParseError, ParseMode, Warning};
+
 pub const UNSPECIFIED_FSP: i8 = -1;
 pub const MAX_FSP: i8 = 6;
 pub const MIN_FSP: i8 = 0;
@@ -10,10 +14,11 @@ pub const DEFAULT_FSP: i8 = 0;
 pub const NANOS_PER_SEC: u64 = 1_000_000_000;
 pub const NANO_WIDTH: u32 = 9;
 const SECS_PER_HOUR: u64 = 3600;
-const MAX_TIME_IN_SECS: u64 = 838 * SECS_PER_HOUR + 59 * SECS_PER_MINUTE + 59;
+const MAX_HOURS: u64 = 838;
+const MAX_TIME_IN_SECS: u64 = MAX_HOURS * SECS_PER_HOUR + 59 * SECS_PER_MINUTE + 59;
 const SECS_PER_MINUTE: u64 = 60;
 
-type Result<T> = std::result::Result<T, &'static str>;
+type Result<T> = std::result::Result<T, ParseError>;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Duration {
@@ -31,7 +36,7 @@ use std::time::Duration as StdDuration;
 fn check_dur(dur: &StdDuration) -> Result<()> {
     let secs = dur.as_secs();
     if secs > MAX_TIME_IN_SECS || secs == MAX_TIME_IN_SECS && dur.subsec_nanos() > 0 {
-        return Err("invalid time");
+        return Err(ParseError::HourOutOfRange(0));
     }
     Ok(())
 }
@@ -41,8 +46,8 @@ fn parse_frac(s: &[u8], fsp: u8) -> Result<u32> {
         return Ok(0);
     }
 
-    if s.iter().any(|&c| c < b'0' || c > b'9') {
-        return Err("Error");
+    if let Some(pos) = s.iter().position(|c| !c.is_ascii_digit()) {
+        return Err(ParseError::InvalidCharacter(pos));
     }
     let res = s
         .iter()
@@ -63,12 +68,72 @@ fn check_fsp(fsp: i8) -> Result<u8> {
     if fsp == UNSPECIFIED_FSP {
         return Ok(DEFAULT_FSP as u8);
     }
-    if fsp > MAX_FSP || fsp < MIN_FSP {
-        return Err("Invalid fsp");
+    if !(MIN_FSP..=MAX_FSP).contains(&fsp) {
+        return Err(ParseError::InvalidFsp(fsp));
     }
     Ok(fsp as u8)
 }
 
+/// Parse `part`, a sub-slice of `input`, as a decimal number. Too many digits saturate at
+/// `u64::MAX`, which the range checks then reject, or clamp with `ParseMode::Truncate`.
+fn parse_num(input: &[u8], part: &[u8]) -> Result<u64> {
+    let offset = part.as_ptr() as usize - input.as_ptr() as usize;
+    if let Some(pos) = part.iter().position(|c| !c.is_ascii_digit()) {
+        return Err(ParseError::InvalidCharacter(offset + pos));
+    }
+    if part.is_empty() {
+        return Err(ParseError::InvalidCharacter(offset));
+    }
+    Ok(part.iter().fold(0, |n: u64, &c| {
+        n.saturating_mul(10).saturating_add(u64::from(c - b'0'))
+    }))
+}
+
+/// The maximum value of a field and the error to report when it is exceeded.
+type Field = (u64, fn(usize) -> ParseError);
+
+const MINUTE: Field = (59, ParseError::MinuteOutOfRange);
+const SECOND: Field = (59, ParseError::SecondOutOfRange);
+
+/// Check `value` against `field`, reporting an error at `offset`, the start of the field.
+fn check_field(value: u64, (max, err): Field, offset: usize) -> Result<u64> {
+    if value > max {
+        Err(err(offset))
+    } else {
+        Ok(value)
+    }
+}
+
+/// `strptime` does not tell why it failed, accepts one-digit fields and ignores anything after
+/// the last one, so check that `part` is exactly the two-digit `fields` separated by colons
+/// before handing it over.
+fn check_fields(input: &[u8], part: &[u8], fields: &[Field]) -> Result<()> {
+    let offset = part.as_ptr() as usize - input.as_ptr() as usize;
+    let mut pos = 0;
+    for (i, &field) in fields.iter().enumerate() {
+        if i > 0 {
+            if part.get(pos) != Some(&b':') {
+                return Err(ParseError::InvalidCharacter(offset + pos));
+            }
+            pos += 1;
+        }
+        let digits = part[pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let value = parse_num(input, &part[pos..pos + digits])?;
+        check_field(value, field, offset + pos)?;
+        if digits != 2 {
+            return Err(ParseError::InvalidCharacter(offset + pos + digits.min(2)));
+        }
+        pos += digits;
+    }
+    if pos < part.len() {
+        return Err(ParseError::InvalidCharacter(offset + pos));
+    }
+    Ok(())
+}
+
 impl Duration {
     pub fn new(dur: StdDuration, neg: bool, fsp: i8) -> Result<Duration> {
         check_dur(&dur)?;
@@ -88,73 +153,97 @@ impl Duration {
         }
     }
 
-    pub fn parse(mut s: &[u8], fsp: i8) -> Result<Duration> {
+    pub fn parse(input: &[u8], fsp: i8) -> Result<Duration> {
+        Duration::parse_with_ctx(&mut EvalContext::default(), input, fsp)
+    }
+
+    /// Parse a TIME value in the `ParseMode` of `ctx`, recording the warnings raised on the
+    /// way into it. Nothing is recorded if parsing fails.
+    pub fn parse_with_ctx(ctx: &mut EvalContext, input: &[u8], fsp: i8) -> Result<Duration> {
         let fsp = check_fsp(fsp)?;
+        let truncate = ctx.mode == ParseMode::Truncate;
+        let offset = |part: &[u8]| part.as_ptr() as usize - input.as_ptr() as usize;
+        let hour = |part: &[u8], hour: u64| check_hour_with_mode(hour, ctx.mode, offset(part));
+        let mut s = trim_input(input)?;
 
-        let (mut neg, mut day, mut frac) = (false, None, 0);
+        let (mut neg, mut day, mut frac, mut truncated) = (false, None, 0, false);
 
-        if s.is_empty() {
-            return Ok(Duration::zero());
-        } else if s[0] == b'-' {
+        if s[0] == b'-' {
             s = &s[1..];
             neg = true;
         }
+        // A value out of range as a whole is reported at its most significant field.
+        let start = offset(s);
 
-        let mut parts = s.splitn(2, |c| *c == b' ');
-        s = parts.next().unwrap();
-        if let Some(remain) = parts.next() {
-            let day_str = str::from_utf8(s).map_err(|_| "fail to parse day")?;
-            day = Some(u64::from_str_radix(day_str, 10).map_err(|_| "fail to parse day")?);
-            s = remain;
+        let (day_part, time) = split_day(s).map_err(|e| e.at(start + e.offset()))?;
+        if let Some(day_part) = day_part {
+            day = Some(parse_num(input, day_part)?);
         }
+        s = time;
 
         let mut parts = s.splitn(2, |c| *c == b'.');
         s = parts.next().unwrap();
         if let Some(frac_part) = parts.next() {
-            frac = parse_frac(frac_part, fsp)?;
+            frac = parse_frac(frac_part, fsp).map_err(|e| e.at(offset(frac_part) + e.offset()))?;
             frac *= 10u32.pow(NANO_WIDTH - u32::from(fsp));
+            truncated = frac_part.iter().skip(fsp as usize).any(|&c| c != b'0');
         }
 
+        if s.last() == Some(&b':') {
+            return Err(ParseError::DanglingColon(offset(s) + s.len() - 1));
+        }
         let mut parts = s.splitn(2, |c| *c == b':');
         s = parts.next().unwrap();
-        let s_str = str::from_utf8(s).map_err(|_| "")?;
         let mut secs;
         match parts.next() {
             Some(remain) => {
-                let remain_str = str::from_utf8(remain).map_err(|_| "")?;
-                let t = match remain.len() {
-                    5 => time::strptime(remain_str, "%M:%S"),
-                    2 => time::strptime(remain_str, "%M"),
-                    _ => return Err(""),
-                }
-                .map_err(|_| "")?;
-                secs = tm_to_secs(t);
-                secs += u64::from_str_radix(s_str, 10).map_err(|_| "")? * SECS_PER_HOUR;
+                let (format, fields): (_, &[Field]) = if remain.contains(&b':') {
+                    ("%M:%S", &[MINUTE, SECOND])
+                } else {
+                    ("%M", &[MINUTE])
+                };
+                let hour = hour(s, parse_num(input, s)?)?;
+                check_fields(input, remain, fields)?;
+                let remain_str = str::from_utf8(remain)
+                    .map_err(|_| ParseError::InvalidCharacter(offset(remain)))?;
+                let t = time::strptime(remain_str, format)
+                    .map_err(|_| ParseError::InvalidCharacter(offset(remain)))?;
+                secs = tm_to_secs(t) + hour * SECS_PER_HOUR;
             }
             None if day.is_some() => {
-                secs = u64::from_str_radix(s_str, 10).map_err(|_| "")? * SECS_PER_HOUR;
+                secs = hour(s, parse_num(input, s)?)? * SECS_PER_HOUR;
             }
             None => {
-                let t = match s.len() {
-                    6 => time::strptime(s_str, "%H%M%S"),
-                    4 => time::strptime(s_str, "%M%S"),
-                    2 => time::strptime(s_str, "%S"),
-                    _ => return Err(""),
-                }
-                .map_err(|_| "")?;
-                secs = tm_to_secs(t);
+                let (hour, minute, second) =
+                    split_hhmmss(parse_num(input, s)?, ctx.mode, offset(s), s.len())?;
+                secs = hour * SECS_PER_HOUR + minute * SECS_PER_MINUTE + second;
             }
         }
 
         if let Some(day) = day {
-            secs += day * SECS_PER_HOUR * 24;
+            secs = secs.saturating_add(day.saturating_mul(SECS_PER_HOUR * 24));
         }
 
-        let dur = StdDuration::new(secs, frac);
-        Duration::new(dur, neg, fsp as i8)
+        let dur = StdDuration::new(secs.min(MAX_TIME_IN_SECS + 1), frac);
+        let (duration, clamped) = match Duration::new(dur, neg, fsp as i8) {
+            Err(ParseError::HourOutOfRange(_)) if truncate => {
+                let max = StdDuration::from_secs(MAX_TIME_IN_SECS);
+                (Duration::new(max, neg, fsp as i8)?, true)
+            }
+            result => (result.map_err(|e| e.at(start))?, false),
+        };
+        if truncated {
+            ctx.warn(Warning::TruncatedFraction);
+        }
+        if clamped {
+            ctx.warn(Warning::ClampedRange);
+        }
+        Ok(duration)
     }
 }
 
+duration_common::impl_parse_prefix!(Duration);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,4 +267,83 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_parse_num() {
+        let input = b"12 3x 99999999999999999999";
+        assert_eq!(parse_num(input, &input[..2]), Ok(12));
+        assert_eq!(
+            parse_num(input, &input[3..5]),
+            Err(ParseError::InvalidCharacter(4))
+        );
+        assert_eq!(
+            parse_num(input, &input[3..3]),
+            Err(ParseError::InvalidCharacter(3))
+        );
+        assert_eq!(parse_num(input, &input[6..]), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_check_fields() {
+        let fields = &[MINUTE, SECOND];
+        let cases = vec![
+            ("34:56", Ok(())),
+            ("60:00", Err(ParseError::MinuteOutOfRange(0))),
+            ("34:60", Err(ParseError::SecondOutOfRange(3))),
+            ("34:567", Err(ParseError::SecondOutOfRange(3))),
+            ("34:5x", Err(ParseError::InvalidCharacter(4))),
+            ("34:5", Err(ParseError::InvalidCharacter(4))),
+            ("3:45", Err(ParseError::InvalidCharacter(1))),
+            ("34", Err(ParseError::InvalidCharacter(2))),
+            ("34:56:78", Err(ParseError::InvalidCharacter(5))),
+        ];
+        for (part, expected) in cases {
+            let input = part.as_bytes();
+            assert_eq!(check_fields(input, input, fields), expected, "{}", part);
+        }
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+        let cases = vec![
+            ("12:34:56abc", Ok(8)),
+            ("1 12:34:", Ok(7)),
+            ("12:34:56.5x", Ok(10)),
+            ("abc", Err(ParseError::InvalidCharacter(0))),
+            ("839:00:00abc", Err(ParseError::HourOutOfRange(0))),
+        ];
+        for (input, expected) in cases {
+            let actual = Duration::parse_prefix(input.as_bytes(), 0).map(|(_, len)| len);
+            assert_eq!(actual, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        for &(s, fsp, mode, len, warnings) in duration_common::testdata::PARSE_WARNINGS {
+            let mut ctx = EvalContext::new(mode);
+            let (_, read) = Duration::parse_prefix_with_ctx(&mut ctx, s.as_bytes(), fsp).unwrap();
+            assert_eq!((read, ctx.warnings()), (len, warnings), "{}", s);
+        }
+        let mut ctx = EvalContext::new(ParseMode::Truncate);
+        let duration = Duration::parse_with_ctx(&mut ctx, b"-1000:00:00.5", 1).unwrap();
+        let max = StdDuration::from_secs(MAX_TIME_IN_SECS);
+        assert_eq!((duration.dur, duration.neg), (max, true));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        for &(s, fsp, err) in duration_common::testdata::PARSE_ERRORS {
+            assert_eq!(
+                Duration::parse(s.as_bytes(), fsp).unwrap_err(),
+                err,
+                "{}",
+                s
+            );
+        }
+        // Unlike the other crates, the minute and second must have two digits.
+        assert_eq!(
+            Duration::parse(b"12:3:04", 0).unwrap_err(),
+            ParseError::InvalidCharacter(4)
+        );
+    }
 }
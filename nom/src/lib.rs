@@ -4,13 +4,25 @@ extern crate test;
 
 use bitfield::bitfield;
 use nom::character::complete::{digit0, digit1, multispace0, multispace1};
+use nom::error::ErrorKind;
 use nom::{
     alt_complete, call, char, complete, cond_with_error, do_parse, eof, map, map_res, named,
     named_args, opt, peek, preceded, tag,
 };
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::time::Duration as StdDuration;
 //use std::str;
-type Result<T> = std::result::Result<T, ()>;
+
+use duration_common::{
+    check_hour_with_mode, micro_bits_to_nano_bits, nano_bits_to_micro_bits, split_hhmmss,
+    split_time_number, trim_input, Decimal,
+};
+
+pub use duration_common::{EvalContext, ParseError, ParseMode, TimeFields, TimeStr, Warning};
+
+type Result<T> = std::result::Result<T, ParseError>;
 
 const TEN_POW: &[u32] = &[
     1, 10, 100, 1000, 10000, 100000, 1000000, 10000000, 100000000, 1000000000,
@@ -31,7 +43,7 @@ fn check_fsp(fsp: i8) -> Result<u8> {
         return Ok(DEFAULT_FSP as u8);
     }
     if fsp > MAX_FSP || fsp < MIN_FSP {
-        return Err(());
+        return Err(ParseError::InvalidFsp(fsp));
     }
     Ok(fsp as u8)
 }
@@ -39,7 +51,7 @@ fn check_fsp(fsp: i8) -> Result<u8> {
 #[inline]
 fn check_hour(hour: u32) -> Result<u32> {
     if hour > MAX_HOURS {
-        Err(())
+        Err(ParseError::HourOutOfRange(0))
     } else {
         Ok(hour)
     }
@@ -48,7 +60,7 @@ fn check_hour(hour: u32) -> Result<u32> {
 #[inline]
 fn check_minute(minute: u32) -> Result<u32> {
     if minute > MAX_MINUTES {
-        Err(())
+        Err(ParseError::MinuteOutOfRange(0))
     } else {
         Ok(minute)
     }
@@ -57,22 +69,33 @@ fn check_minute(minute: u32) -> Result<u32> {
 #[inline]
 fn check_second(second: u32) -> Result<u32> {
     if second > MAX_SECONDS {
-        Err(())
+        Err(ParseError::SecondOutOfRange(0))
     } else {
         Ok(second)
     }
 }
 
+/// Reject a value past `838:59:59`, which `838:59:59` with a fraction is too.
+#[inline]
+fn check_max(hour: u32, minute: u32, second: u32, micros: u32) -> Result<()> {
+    if (hour, minute, second, micros) > (MAX_HOURS, MAX_MINUTES, MAX_SECONDS, 0) {
+        Err(ParseError::HourOutOfRange(0))
+    } else {
+        Ok(())
+    }
+}
+
 fn buf_to_int(buf: &[u8]) -> u32 {
     buf.iter().fold(0, |acc, c| acc * 10 + (c - b'0') as u32)
 }
 
+// A number too long for `u32` saturates, it is out of range whatever field it is.
 named!(
     read_int<u32>,
-    map_res!(digit1, |buf: &[u8]| if buf.len() > 7 {
-        Err(())
+    map!(digit1, |buf: &[u8]| if buf.len() > 7 {
+        u32::MAX
     } else {
-        Ok(buf_to_int(buf))
+        buf_to_int(buf)
     })
 );
 
@@ -114,14 +137,16 @@ named!(
     ))
 );
 
+// The fields are returned as raw digits, so that `Duration::parse` can range check them and
+// report where an invalid one starts.
 named!(
-    hhmmss<(Option<u32>, Option<u32>, Option<u32>)>,
+    hhmmss<(Option<&[u8]>, Option<&[u8]>, Option<&[u8]>)>,
     do_parse!(
-        hour: opt!(map_res!(read_int, check_hour))
+        hour: opt!(digit1)
             >> has_mintue: map!(opt!(complete!(char!(':'))), |flag| flag.is_some())
-            >> minute: cond_with_error!(has_mintue, map_res!(read_int, check_minute))
+            >> minute: cond_with_error!(has_mintue, digit1)
             >> has_second: map!(opt!(complete!(char!(':'))), |flag| flag.is_some())
-            >> second: cond_with_error!(has_second, map_res!(read_int, check_second))
+            >> second: cond_with_error!(has_second, digit1)
             >> (hour, minute, second)
     )
 );
@@ -134,9 +159,9 @@ named_args!(
 named_args!(parse(fsp: u8)<
             (bool,          // neg
              Option<u32>,   // day
-             Option<u32>,   // hour
-             Option<u32>,   // minute
-             Option<u32>,   // second
+             Option<&[u8]>, // hour
+             Option<&[u8]>, // minute
+             Option<&[u8]>, // second
              Option<u32>)>, // fraction
 
             do_parse!(
@@ -149,6 +174,23 @@ named_args!(parse(fsp: u8)<
                 >> eof!()
                 >> (neg, day, hhmmss.0, hhmmss.1, hhmmss.2, fraction)));
 
+/// Translate the position at which the grammar gave up into a `ParseError`.
+fn parse_error(input: &[u8], err: nom::Err<(&[u8], ErrorKind)>) -> ParseError {
+    match err {
+        nom::Err::Error((rest, kind)) | nom::Err::Failure((rest, kind)) => {
+            let offset = input.len() - rest.len();
+            if kind == ErrorKind::MapRes {
+                ParseError::Overflow(offset)
+            } else if rest.is_empty() && input.last() == Some(&b':') {
+                ParseError::DanglingColon(offset - 1)
+            } else {
+                ParseError::InvalidCharacter(offset)
+            }
+        }
+        nom::Err::Incomplete(_) => ParseError::InvalidCharacter(input.len()),
+    }
+}
+
 bitfield! {
     #[derive(Clone, Copy)]
     pub struct Duration(u64);
@@ -169,30 +211,163 @@ bitfield! {
     pub u8, fsp, set_fsp: 7, 0;
 }
 
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_time_str(), f)
+    }
+}
+
+/// Values are compared by what they represent: `fsp` is ignored and negative values sort below
+/// positive ones.
+impl PartialEq for Duration {
+    fn eq(&self, other: &Duration) -> bool {
+        self.to_nanos() == other.to_nanos()
+    }
+}
+
+impl Eq for Duration {}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Duration) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Duration) -> Ordering {
+        self.to_nanos().cmp(&other.to_nanos())
+    }
+}
+
+impl Hash for Duration {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_nanos().hash(state);
+    }
+}
+
 impl Duration {
+    /// The packed representation, for persisting the value.
+    #[inline]
+    pub fn to_bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Restore a value persisted with `to_bits`, rejecting bit patterns no valid value has.
+    pub fn from_bits(bits: u64) -> Result<Duration> {
+        TimeFields::from_micro_bits(bits)?;
+        Ok(Duration(bits))
+    }
+
+    /// The bits of the same value in the nanosecond layout of dfa and pull-4427.
+    pub fn to_nano_bits(&self) -> Result<u64> {
+        micro_bits_to_nano_bits(self.0)
+    }
+
+    /// Restore a value persisted in the nanosecond layout of dfa and pull-4427. Fails if its
+    /// fraction is finer than microseconds.
+    pub fn from_nano_bits(bits: u64) -> Result<Duration> {
+        nano_bits_to_micro_bits(bits).map(Duration)
+    }
+    /// Append the value in MySQL's `[-]hh:mm:ss[.fff]` format, like `Display` but without
+    /// allocating.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.to_time_str().as_bytes());
+    }
+
+    /// The value in MySQL's `[-]hh:mm:ss[.fff]` format, with exactly `fsp` fraction digits.
+    #[inline]
+    pub fn to_time_str(&self) -> TimeStr {
+        TimeStr::new(
+            self.neg(),
+            u64::from(self.hour()),
+            u64::from(self.minute()),
+            u64::from(self.second()),
+            u64::from(self.nano()) * 1000,
+            self.fsp(),
+        )
+    }
+
     pub fn parse(input: &[u8], fsp: i8) -> Result<Duration> {
-        if input.is_empty() {
-            return Err(());
-        }
+        Duration::parse_with_ctx(&mut EvalContext::default(), input, fsp)
+    }
+
+    /// Parse a TIME value in the `ParseMode` of `ctx`, recording the warnings raised on the
+    /// way into it. Nothing is recorded if parsing fails.
+    pub fn parse_with_ctx(ctx: &mut EvalContext, input: &[u8], fsp: i8) -> Result<Duration> {
+        trim_input(input)?;
         let fsp = check_fsp(fsp)?;
-        let (_, (neg, mut day, mut hour, mut minute, mut second, fraction)) =
-            parse(input, fsp).map_err(|_| ())?;
+        let truncate = ctx.mode == ParseMode::Truncate;
+        let (_, (neg, mut day, hour, minute, second, fraction)) =
+            parse(input, fsp).map_err(|e| parse_error(input, e))?;
+
+        // Errors are reported at the start of their field, and a value out of range as a whole
+        // at its most significant one, the first digits of the input.
+        let start = input.iter().position(u8::is_ascii_digit).unwrap_or(0);
+        let field = |buf: Option<&[u8]>, check: &dyn Fn(u32) -> Result<u32>| {
+            buf.map(|buf| {
+                let offset = buf.as_ptr() as usize - input.as_ptr() as usize;
+                let value = if buf.len() > 7 {
+                    u32::MAX
+                } else {
+                    buf_to_int(buf)
+                };
+                check(value).map_err(|e| e.at(offset))
+            })
+            .transpose()
+        };
+        let check_hours =
+            |hour: u32| check_hour_with_mode(u64::from(hour), ctx.mode, 0).map(|hour| hour as u32);
+        let mut hour = field(hour, &check_hours)?;
+        let mut minute = field(minute, &check_minute)?;
+        let mut second = field(second, &check_second)?;
 
         if day.is_some() && hour.is_none() {
+            // `[h..]hhmmss` comes out of `parse` as a day without a time.
             let block = day.take().unwrap();
-            hour = Some(block / 10_000);
-            minute = Some(block / 100 % 100);
-            second = Some(block % 100);
+            let len = input[start..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            let (h, m, s) = split_hhmmss(u64::from(block), ctx.mode, start, len)?;
+            hour = Some(h as u32);
+            minute = Some(m as u32);
+            second = Some(s as u32);
         }
 
         let (hour, minute, second, fraction) = (
-            hour.unwrap_or(0) + day.unwrap_or(0) * 24,
+            hour.unwrap_or(0)
+                .saturating_add(day.unwrap_or(0).saturating_mul(24)),
             minute.unwrap_or(0),
             second.unwrap_or(0),
             fraction.unwrap_or(0),
         );
 
-        Duration::build(neg, hour, minute, second, fraction, fsp)
+        let built = check_hour(hour)
+            .and_then(|hour| Duration::build(neg, hour, minute, second, fraction, fsp));
+        let (duration, clamped) = match built {
+            Err(ParseError::HourOutOfRange(_)) if truncate => (
+                Duration::build(neg, MAX_HOURS, MAX_MINUTES, MAX_SECONDS, 0, fsp)?,
+                true,
+            ),
+            result => (result.map_err(|e| e.at(start))?, false),
+        };
+
+        // `read_int_with_fsp` keeps a single digit beyond `fsp` for rounding, look at the
+        // fraction again to tell whether anything was lost.
+        if let Some(dot) = input.iter().position(|&c| c == b'.') {
+            let truncated = input[dot + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .skip(fsp as usize)
+                .any(|&c| c != b'0');
+            if truncated {
+                ctx.warn(Warning::TruncatedFraction);
+            }
+        }
+        if clamped {
+            ctx.warn(Warning::ClampedRange);
+        }
+        Ok(duration)
     }
 
     pub fn new(duration: StdDuration, neg: bool, fsp: i8) -> Result<Duration> {
@@ -215,6 +390,55 @@ impl Duration {
         )
     }
 
+    /// Convert a float to a TIME value following MySQL's numeric rules: the integer part is read
+    /// as `[-]hhmmss`, or when it is too large for that, as a `YYYYMMDDhhmmss` datetime number of
+    /// which only the time of day is kept. The fraction is rounded to `fsp`.
+    pub fn from_f64(value: f64, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        Duration::from_decimal(Decimal::from_f64(value)?, fsp)
+    }
+
+    /// Convert the text of a decimal like `123456.789` or `1.23456789e5` to a TIME value like
+    /// `from_f64`.
+    pub fn from_decimal_str(s: &str, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        Duration::from_decimal(Decimal::parse(s.as_bytes())?, fsp)
+    }
+
+    fn from_decimal(decimal: Decimal, fsp: u8) -> Result<Duration> {
+        let number = split_time_number(decimal.neg, decimal.integer)?;
+        let hour = check_hour((number / 10000) as u32)?;
+        let minute = check_minute((number / 100 % 100) as u32)?;
+        let second = check_second((number % 100) as u32)?;
+        let duration =
+            Duration::build(decimal.neg, hour, minute, second, decimal.nanos as u32, fsp)?;
+        Ok(duration.clear_neg_if_zero())
+    }
+
+    fn from_std(duration: StdDuration, neg: bool, fsp: i8) -> Result<Duration> {
+        if duration.as_secs() / 3600 > u64::from(MAX_HOURS) {
+            return Err(ParseError::HourOutOfRange(0));
+        }
+        Duration::new(duration, neg, fsp).map(Duration::clear_neg_if_zero)
+    }
+
+    /// The value as a signed number of nanoseconds.
+    pub fn to_nanos(&self) -> i64 {
+        let secs = (i64::from(self.hour()) * 60 + i64::from(self.minute())) * 60
+            + i64::from(self.second());
+        let nanos = secs * 1_000_000_000 + i64::from(self.nano()) * 1000;
+        if self.neg() {
+            -nanos
+        } else {
+            nanos
+        }
+    }
+
+    /// The value as a signed number of microseconds.
+    pub fn to_micros(&self) -> i64 {
+        self.to_nanos() / 1000
+    }
+
     /// Build a `Duration` with details, truncate `fraction` with `fsp` and take the produced carry
     /// NOTE: the function assumes that the value of `hour/minute/second/fsp` is valid,
     /// so before you call function `build`, make sure you have checked their validity.
@@ -237,8 +461,8 @@ impl Duration {
             hour += minute / 60;
             second %= 60;
             minute %= 60;
-            hour = check_hour(hour)?;
         }
+        check_max(hour, minute, second, fraction)?;
 
         let mut duration = Duration(0);
         duration.set_neg(neg);
@@ -251,6 +475,13 @@ impl Duration {
     }
 }
 
+duration_common::impl_parse_prefix!(Duration);
+duration_common::impl_signed_conversions!(Duration);
+#[cfg(feature = "serde")]
+duration_common::impl_serde!(Duration);
+#[cfg(all(test, feature = "serde"))]
+duration_common::serde_round_trip_test!(Duration);
+
 #[test]
 fn it_works() {
     println!("{:#?}", Duration::parse(b"1:2:3.123", 6));
@@ -275,6 +506,244 @@ fn it_works() {
     println!("{:#?}", Duration::parse(b"", 2));
 }
 
+#[test]
+fn nanos_and_micros() {
+    let duration = Duration::parse(b"-1 2:3:4.567891", 6).unwrap();
+    assert_eq!(duration.to_nanos(), -93_784_567_891_000);
+    assert_eq!(duration.to_micros(), -93_784_567_891);
+    assert_eq!(
+        Duration::from_nanos(-93_784_567_891_000, 6).unwrap().0,
+        duration.0
+    );
+    assert_eq!(
+        Duration::from_micros(-93_784_567_891, 6).unwrap().0,
+        duration.0
+    );
+    assert_eq!(
+        Duration::from_micros(-400_000, 0).unwrap().to_string(),
+        "00:00:00"
+    );
+    assert_eq!(
+        Duration::from_micros(839 * 3600 * 1_000_000, 6).unwrap_err(),
+        ParseError::HourOutOfRange(0)
+    );
+
+    let max = StdDuration::from_secs(838 * 3600 + 59 * 60 + 59);
+    assert_eq!(
+        Duration::from_std(max, true, 6).unwrap().to_string(),
+        "-838:59:59.000000"
+    );
+    assert_eq!(
+        Duration::from_std(max + StdDuration::from_micros(1), false, 6).unwrap_err(),
+        ParseError::HourOutOfRange(0)
+    );
+    assert_eq!(
+        Duration::from_std(max + StdDuration::from_nanos(400), false, 6)
+            .unwrap()
+            .to_string(),
+        "838:59:59.000000"
+    );
+}
+
+#[test]
+fn bits() {
+    let duration = Duration::parse(b"-1 2:3:4.567891", 6).unwrap();
+    assert_eq!(
+        Duration::from_bits(duration.to_bits()).unwrap().0,
+        duration.0
+    );
+    let nano_bits = duration.to_nano_bits().unwrap();
+    assert_eq!(Duration::from_nano_bits(nano_bits).unwrap().0, duration.0);
+    assert_eq!(
+        Duration::from_bits(duration.to_bits() | 1 << 54).unwrap_err(),
+        ParseError::Overflow(0)
+    );
+}
+
+#[test]
+fn ord_eq_hash() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let hash = |d: &Duration| {
+        let mut hasher = DefaultHasher::new();
+        d.hash(&mut hasher);
+        hasher.finish()
+    };
+    // Ascending values around the boundaries of the packed microsecond fields, both signs.
+    let sorted = [
+        "-838:59:59",
+        "-256:00:00",
+        "-255:59:59.999999",
+        "-1:00:00",
+        "-0:59:59.999999",
+        "-0:00:01",
+        "-0:00:00.999999",
+        "-0:00:00.000001",
+        "0",
+        "0:00:00.000001",
+        "0:00:00.999999",
+        "0:00:01",
+        "0:59:59.999999",
+        "1:00:00",
+        "255:59:59.999999",
+        "256:00:00",
+        "838:59:59",
+    ];
+    let values: Vec<_> = sorted
+        .iter()
+        .map(|s| Duration::parse(s.as_bytes(), 6).unwrap())
+        .collect();
+    for (i, a) in values.iter().enumerate() {
+        for (j, b) in values.iter().enumerate() {
+            assert_eq!(a.cmp(b), i.cmp(&j), "{} {}", a, b);
+        }
+    }
+
+    // xorshift64*, enough to spread values over the whole range without a dependency.
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut random = |n: u64| {
+        seed ^= seed >> 12;
+        seed ^= seed << 25;
+        seed ^= seed >> 27;
+        seed.wrapping_mul(0x2545_f491_4f6c_dd1d) % n
+    };
+    let mut values = Vec::new();
+    for _ in 0..1000 {
+        let mut micros = random(838 * 3600 * 1_000_000) as i64;
+        if random(2) == 0 {
+            micros = -micros;
+        }
+        // Whole seconds are the same value with every fsp.
+        let mut fsp = 6;
+        if random(4) == 0 {
+            micros = micros / 1_000_000 * 1_000_000;
+            fsp = random(7) as i8;
+        }
+        let (a, b) = (
+            Duration::from_micros(micros, 6).unwrap(),
+            Duration::from_micros(micros, fsp).unwrap(),
+        );
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        values.push((micros, a));
+    }
+    for pair in values.windows(2) {
+        let ((x, a), (y, b)) = (pair[0], pair[1]);
+        assert_eq!(a.cmp(&b), x.cmp(&y), "{} {}", a, b);
+        assert_eq!(a == b, x == y);
+    }
+    values.sort_by_key(|&(_, d)| d);
+    assert!(values.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+    // The fsp and the sign of zero are not part of the value.
+    let equal = [
+        ("-0:0:0.0", 1, "0", 0),
+        ("12:00:00.0", 1, "12:00:00", 0),
+        ("-838:59:59.000", 3, "-838:59:59", 6),
+        ("1:02:03.5", 1, "1:02:03.500000", 6),
+    ];
+    for &(x, x_fsp, y, y_fsp) in equal.iter() {
+        let (a, b) = (
+            Duration::parse(x.as_bytes(), x_fsp).unwrap(),
+            Duration::parse(y.as_bytes(), y_fsp).unwrap(),
+        );
+        assert_eq!(a, b, "{} {}", x, y);
+        assert_eq!(hash(&a), hash(&b), "{} {}", x, y);
+    }
+}
+
+#[test]
+fn parse_error_offsets() {
+    for &(s, fsp, err) in duration_common::testdata::PARSE_ERRORS {
+        assert_eq!(
+            Duration::parse(s.as_bytes(), fsp).unwrap_err(),
+            err,
+            "{}",
+            s
+        );
+    }
+}
+
+#[test]
+fn parse_warnings() {
+    for &(s, fsp, mode, len, warnings) in duration_common::testdata::PARSE_WARNINGS {
+        let mut ctx = EvalContext::new(mode);
+        let (_, read) = Duration::parse_prefix_with_ctx(&mut ctx, s.as_bytes(), fsp).unwrap();
+        assert_eq!((read, ctx.warnings()), (len, warnings), "{}", s);
+    }
+    let mut ctx = EvalContext::new(ParseMode::Truncate);
+    let duration = Duration::parse_with_ctx(&mut ctx, b"-1000:00:00.5", 1).unwrap();
+    assert_eq!(duration.to_string(), "-838:59:59.0");
+}
+
+#[test]
+fn display() {
+    let cases = vec![
+        ("12:34:56", 0, "12:34:56"),
+        ("  -1   1:2:3.99999  ", 2, "-25:02:04.00"),
+        ("1:2:3.1235", 3, "01:02:03.124"),
+        ("838:59:59", 6, "838:59:59.000000"),
+    ];
+    for (input, fsp, expected) in cases {
+        let duration = Duration::parse(input.as_bytes(), fsp).unwrap();
+        assert_eq!(duration.to_string(), expected);
+        let mut buf = Vec::new();
+        duration.write_to(&mut buf);
+        assert_eq!(buf, expected.as_bytes());
+    }
+}
+
+#[test]
+fn parse_prefix() {
+    let cases = vec![
+        ("12:34:56abc", Ok(((12, 34, 56), 8))),
+        ("  -1 1:2:3.99x", Ok(((25, 2, 3), 13))),
+        ("12:34:", Ok(((12, 34, 0), 5))),
+        ("abc", Err(ParseError::InvalidCharacter(0))),
+    ];
+    for (input, expected) in cases {
+        let actual = Duration::parse_prefix(input.as_bytes(), 2)
+            .map(|(d, len)| ((d.hour(), d.minute(), d.second()), len));
+        assert_eq!(actual, expected, "{}", input);
+    }
+}
+
+#[test]
+fn from_f64_and_decimal_str() {
+    let cases = vec![
+        (
+            123456.789,
+            "123456.789",
+            2,
+            Ok((false, 12, 34, 56, 790_000)),
+        ),
+        (
+            -123456.785,
+            "-1.23456785e5",
+            2,
+            Ok((true, 12, 34, 56, 790_000)),
+        ),
+        (235959.5, "235959.5", 0, Ok((false, 24, 0, 0, 0))),
+        (-0.4, "-0.4", 0, Ok((false, 0, 0, 0, 0))),
+        (
+            8385959.5,
+            "8385959.5",
+            0,
+            Err(ParseError::HourOutOfRange(0)),
+        ),
+        (126000.0, "1.26e5", 0, Err(ParseError::MinuteOutOfRange(0))),
+        (1e20, "1e20", 0, Err(ParseError::Overflow(0))),
+    ];
+    for (float, decimal, fsp, expected) in cases {
+        let fields = |d: Duration| (d.neg(), d.hour(), d.minute(), d.second(), d.nano());
+        assert_eq!(Duration::from_f64(float, fsp).map(fields), expected);
+        assert_eq!(
+            Duration::from_decimal_str(decimal, fsp).map(fields),
+            expected
+        );
+    }
+}
+
 #[bench]
 fn bench_parse(b: &mut test::Bencher) {
     b.iter(|| {
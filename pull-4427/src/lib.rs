@@ -1,7 +1,17 @@
 #![feature(test)]
 extern crate test;
 use bitfield::bitfield;
-use std::str;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::Duration as StdDuration;
+
+use duration_common::{
+    check_hour_with_mode, micro_bits_to_nano_bits, nano_bits_to_micro_bits, split_day,
+    split_hhmmss, split_time_number, trim_input,
+};
+
+pub use duration_common::{EvalContext, ParseError, ParseMode, TimeFields, TimeStr, Warning};
+
 const TEN_POW: &[u32] = &[
     1, 10, 100, 1000, 10000, 100000, 1000000, 10000000, 100000000, 1000000000,
 ];
@@ -18,31 +28,49 @@ const MAX_SECONDS: u64 = 59;
 const MINUTES_PER_HOUR: u64 = 60;
 const SECS_PER_MINUTE: u64 = 60;
 
-type Result<T> = std::result::Result<T, ()>;
+type Result<T> = std::result::Result<T, ParseError>;
+
 bitfield! {
     #[derive(Clone, Copy)]
     pub struct Duration(u64);
     impl Debug;
     #[inline]
-    bool, neg, set_neg: 63;
+    pub bool, neg, _: 63;
+    #[inline]
+    bool, _, set_neg: 63;
+    #[inline]
+    pub u64, hour, _: 62, 53;
+    #[inline]
+    u64, _, set_hour: 62, 53;
     #[inline]
-    u64, hour, set_hour: 62, 53;
+    pub u64, minute, _: 52, 47;
     #[inline]
-    u64, minute, set_minute: 52, 47;
+    u64, _, set_minute: 52, 47;
     #[inline]
-    u64, second, set_second: 46, 41;
+    pub u64, second, _: 46, 41;
     #[inline]
-    u64, nano, set_nano: 40, 9;
+    u64, _, set_second: 46, 41;
+    /// The fraction in nanoseconds.
+    #[inline]
+    pub u64, nano, _: 40, 9;
+    #[inline]
+    u64, _, set_nano: 40, 9;
     #[inline]
     pub u8, fsp, set_fsp: 8, 1;
     #[inline]
     bool, unused, set_unused: 0;
 }
 
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_time_str(), f)
+    }
+}
+
 #[inline]
 fn check_hour(hour: u64) -> Result<u64> {
     if hour > MAX_HOURS {
-        Err(())
+        Err(ParseError::HourOutOfRange(0))
     } else {
         Ok(hour)
     }
@@ -51,7 +79,7 @@ fn check_hour(hour: u64) -> Result<u64> {
 #[inline]
 fn check_minute(minute: u64) -> Result<u64> {
     if minute > MAX_MINUTES {
-        Err(())
+        Err(ParseError::MinuteOutOfRange(0))
     } else {
         Ok(minute)
     }
@@ -60,34 +88,63 @@ fn check_minute(minute: u64) -> Result<u64> {
 #[inline]
 fn check_second(second: u64) -> Result<u64> {
     if second > MAX_SECONDS {
-        Err(())
+        Err(ParseError::SecondOutOfRange(0))
     } else {
         Ok(second)
     }
 }
 
+/// Reject a value past `838:59:59`, which `838:59:59` with a fraction is too.
+#[inline]
+fn check_max(hour: u64, minute: u64, second: u64, nano: u64) -> Result<()> {
+    if (hour, minute, second, nano) > (MAX_HOURS, MAX_MINUTES, MAX_SECONDS, 0) {
+        Err(ParseError::HourOutOfRange(0))
+    } else {
+        Ok(())
+    }
+}
+
 fn check_fsp(fsp: i8) -> Result<u8> {
     if fsp == UNSPECIFIED_FSP {
         return Ok(DEFAULT_FSP as u8);
     }
-    if fsp > MAX_FSP || fsp < MIN_FSP {
-        return Err(());
+    if !(MIN_FSP..=MAX_FSP).contains(&fsp) {
+        return Err(ParseError::InvalidFsp(fsp));
     }
     Ok(fsp as u8)
 }
 
-fn parse_frac(frac: &str, fsp: u8) -> Result<u64> {
+/// Parse `part`, a sub-slice of `input`, as an unsigned integer. On failure the error
+/// points at the offending byte of `input`. Too many digits saturate at `u64::MAX`, which the
+/// range checks then reject, or clamp with `ParseMode::Truncate`.
+fn parse_int(input: &[u8], part: &[u8]) -> Result<u64> {
+    let offset = part.as_ptr() as usize - input.as_ptr() as usize;
+    if part.is_empty() {
+        return Err(ParseError::InvalidCharacter(offset));
+    }
+    part.iter().enumerate().try_fold(0u64, |n, (i, &c)| {
+        if !c.is_ascii_digit() {
+            return Err(ParseError::InvalidCharacter(offset + i));
+        }
+        Ok(n.saturating_mul(10).saturating_add(u64::from(c - b'0')))
+    })
+}
+
+fn parse_frac(frac: &[u8], fsp: u8) -> Result<u64> {
     if frac.is_empty() {
         return Ok(0);
     }
+    // Only the digits up to the rounding one are parsed, the others must be digits too.
+    if let Some(pos) = frac.iter().position(|c| !c.is_ascii_digit()) {
+        return Err(ParseError::InvalidCharacter(pos));
+    }
 
     let fsp = fsp as usize;
-    let mapping = |_| ();
 
     Ok(if frac.len() <= fsp {
-        frac.parse::<u64>().map_err(mapping)? * u64::from(TEN_POW[fsp - frac.len()])
+        parse_int(frac, frac)? * u64::from(TEN_POW[fsp - frac.len()])
     } else {
-        let result = frac[..=fsp].parse::<u64>().map_err(mapping)?;
+        let result = parse_int(frac, &frac[..=fsp])?;
         if result % 10 > 4 {
             result / 10 + 1
         } else {
@@ -101,6 +158,47 @@ impl Duration {
     pub fn zero() -> Self {
         Duration(0)
     }
+
+    /// The packed representation, for persisting the value.
+    #[inline]
+    pub fn to_bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Restore a value persisted with `to_bits`, rejecting bit patterns no valid value has.
+    pub fn from_bits(bits: u64) -> Result<Duration> {
+        TimeFields::from_nano_bits(bits)?;
+        Ok(Duration(bits))
+    }
+
+    /// The bits of the same value in the microsecond layout of duration-nom.
+    pub fn to_micro_bits(&self) -> Result<u64> {
+        nano_bits_to_micro_bits(self.0)
+    }
+
+    /// Restore a value persisted in the microsecond layout of duration-nom.
+    pub fn from_micro_bits(bits: u64) -> Result<Duration> {
+        micro_bits_to_nano_bits(bits).map(Duration)
+    }
+
+    /// Append the value in MySQL's `[-]hh:mm:ss[.fff]` format, like `Display` but without
+    /// allocating.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.to_time_str().as_bytes());
+    }
+
+    /// The value in MySQL's `[-]hh:mm:ss[.fff]` format, with exactly `fsp` fraction digits.
+    #[inline]
+    pub fn to_time_str(&self) -> TimeStr {
+        TimeStr::new(
+            self.neg(),
+            self.hour(),
+            self.minute(),
+            self.second(),
+            self.nano(),
+            self.fsp(),
+        )
+    }
     #[inline]
     fn with_detail(
         neg: bool,
@@ -111,19 +209,17 @@ impl Duration {
         fsp: u8,
     ) -> Result<Duration> {
         let round = u64::from(TEN_POW[NANO_WIDTH as usize - fsp as usize - 1]);
-        if nano / round % 10 > 4 {
-            let padding = round * 10;
-            nano = (nano / padding + 1) * padding;
-        }
+        let padding = round * 10;
+        nano = (nano / padding + u64::from(nano / round % 10 > 4)) * padding;
 
         second += nano / NANOS_PER_SEC;
         minute += second / SECS_PER_MINUTE;
         hour += minute / MINUTES_PER_HOUR;
-        check_hour(hour)?;
 
         nano %= NANOS_PER_SEC;
         second %= SECS_PER_MINUTE;
         minute %= MINUTES_PER_HOUR;
+        check_max(hour, minute, second, nano)?;
 
         let mut duration = Duration(0);
         duration.set_neg(neg);
@@ -135,74 +231,168 @@ impl Duration {
         Ok(duration)
     }
 
-    pub fn parse(mut s: &[u8], fsp: i8) -> Result<Duration> {
-        let fsp = check_fsp(fsp)?;
+    pub fn parse(input: &[u8], fsp: i8) -> Result<Duration> {
+        Duration::parse_with_ctx(&mut EvalContext::default(), input, fsp)
+    }
 
-        if s.is_empty() {
-            let mut zero = Duration::zero();
-            zero.set_fsp(fsp);
-            return Ok(zero);
-        }
+    /// Parse a TIME value in the `ParseMode` of `ctx`, recording the warnings raised on the
+    /// way into it. Nothing is recorded if parsing fails.
+    pub fn parse_with_ctx(ctx: &mut EvalContext, input: &[u8], fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        let truncate = ctx.mode == ParseMode::Truncate;
+        let offset = |part: &[u8]| part.as_ptr() as usize - input.as_ptr() as usize;
 
+        let mut s = trim_input(input)?;
         let neg = if s[0] == b'-' {
             s = &s[1..];
             true
         } else {
             false
         };
+        // A value out of range as a whole is reported at its most significant field.
+        let start = offset(s);
 
-        let mut day = None;
-        let mut parts = s.splitn(2, |c| *c == b' ');
-        s = parts.next().unwrap();
-        if let Some(part) = parts.next() {
-            day = Some(
-                unsafe { str::from_utf8_unchecked(s) }
-                    .parse::<u64>()
-                    .map_err(|_| ())?,
-            );
-            s = part;
-        }
+        let (day, time) = split_day(s).map_err(|e| e.at(start + e.offset()))?;
+        let day = day.map(|day| parse_int(input, day)).transpose()?;
+        s = time;
 
-        let mut nano = 0;
+        let (mut nano, mut truncated) = (0, false);
         let mut parts = s.splitn(2, |c| *c == b'.');
         s = parts.next().unwrap();
         if let Some(frac) = parts.next() {
-            nano = parse_frac(unsafe { str::from_utf8_unchecked(frac) }, fsp)?
+            nano = parse_frac(frac, fsp).map_err(|e| e.at(offset(frac) + e.offset()))?
                 * u64::from(TEN_POW[NANO_WIDTH as usize - fsp as usize]);
+            truncated = frac.iter().skip(fsp as usize).any(|&c| c != b'0');
         }
         let mut parts = s.splitn(3, |c| *c == b':');
-        let first = parts.next().ok_or(())?;
+        let first = parts.next().unwrap();
 
-        let first_try = unsafe { str::from_utf8_unchecked(first) }.parse::<u64>();
-        let mut hour;
-        let (mut minute, mut second) = (0, 0);
-        match parts.next() {
+        let first_try = parse_int(input, first);
+        let check_first = |hour: u64| check_hour_with_mode(hour, ctx.mode, offset(first));
+        let (mut hour, minute, second) = match parts.next() {
             Some(part) => {
-                hour = first_try.map_err(|_| ())?;
-                minute = unsafe { str::from_utf8_unchecked(part) }
-                    .parse::<u64>()
-                    .map_err(|_| ())
-                    .and_then(check_minute)?;
+                if part.is_empty() {
+                    return Err(ParseError::DanglingColon(offset(part) - 1));
+                }
+                let hour = first_try.and_then(check_first)?;
+                let minute = parse_int(input, part)
+                    .and_then(|m| check_minute(m).map_err(|e| e.at(offset(part))))?;
 
+                let mut second = 0;
                 if let Some(part) = parts.next() {
-                    second = unsafe { str::from_utf8_unchecked(part) }
-                        .parse::<u64>()
-                        .map_err(|_| ())
-                        .and_then(check_second)?;
+                    if part.is_empty() {
+                        return Err(ParseError::DanglingColon(offset(part) - 1));
+                    }
+                    second = parse_int(input, part)
+                        .and_then(|s| check_second(s).map_err(|e| e.at(offset(part))))?;
                 }
+                (hour, minute, second)
             }
-            None if day.is_some() => {
-                hour = first_try.map_err(|_| ())?;
-            }
-            None => {
-                let time = first_try.map_err(|_| ())?;
-                second = check_second(time % 100)?;
-                minute = check_minute(time / 100 % 100)?;
-                hour = time / 1_00_00;
-            }
+            None if day.is_some() => (first_try.and_then(check_first)?, 0, 0),
+            None => split_hhmmss(first_try?, ctx.mode, offset(first), first.len())?,
+        };
+        hour = hour.saturating_add(day.unwrap_or(0).saturating_mul(24));
+        let (duration, clamped) = match Duration::with_detail(neg, hour, minute, second, nano, fsp)
+        {
+            Err(ParseError::HourOutOfRange(_)) if truncate => (
+                Duration::with_detail(neg, MAX_HOURS, MAX_MINUTES, MAX_SECONDS, 0, fsp)?,
+                true,
+            ),
+            result => (result.map_err(|e| e.at(start))?, false),
+        };
+        if truncated {
+            ctx.warn(Warning::TruncatedFraction);
+        }
+        if clamped {
+            ctx.warn(Warning::ClampedRange);
+        }
+        Ok(duration)
+    }
+
+    /// Convert an integer to a TIME value following MySQL's numeric rules: it is read as
+    /// `[-]hhmmss`, or when it is too large for that, as a `YYYYMMDDhhmmss` (or `YYMMDDhhmmss`)
+    /// datetime number of which only the time of day is kept.
+    pub fn from_i64(value: i64, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        let neg = value < 0;
+        let number = split_time_number(neg, value.unsigned_abs())?;
+        let second = check_second(number % 100)?;
+        let minute = check_minute(number / 100 % 100)?;
+        Duration::with_detail(neg, number / 10000, minute, second, 0, fsp)
+    }
+
+    /// Convert a `std::time::Duration` and its sign, rounding the fraction to `fsp`.
+    pub fn from_std(duration: StdDuration, neg: bool, fsp: i8) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        let secs = duration.as_secs();
+        let duration = Duration::with_detail(
+            neg,
+            secs / (SECS_PER_MINUTE * MINUTES_PER_HOUR),
+            secs / SECS_PER_MINUTE % MINUTES_PER_HOUR,
+            secs % SECS_PER_MINUTE,
+            u64::from(duration.subsec_nanos()),
+            fsp,
+        )?;
+        Ok(duration.clear_neg_if_zero())
+    }
+
+    /// The absolute value as a `std::time::Duration`, and whether it is negative.
+    pub fn to_std(&self) -> (StdDuration, bool) {
+        let secs =
+            (self.hour() * MINUTES_PER_HOUR + self.minute()) * SECS_PER_MINUTE + self.second();
+        (StdDuration::new(secs, self.nano() as u32), self.neg())
+    }
+
+    /// Build a value from its components, rejecting any of them out of range. `micros` is the
+    /// fraction in microseconds and gets rounded to `fsp`.
+    pub fn from_parts(
+        neg: bool,
+        hour: u64,
+        minute: u64,
+        second: u64,
+        micros: u64,
+        fsp: i8,
+    ) -> Result<Duration> {
+        let fsp = check_fsp(fsp)?;
+        if micros >= NANOS_PER_SEC / 1000 {
+            return Err(ParseError::Overflow(0));
+        }
+        check_hour(hour)?;
+        check_minute(minute)?;
+        check_second(second)?;
+        let duration = Duration::with_detail(neg, hour, minute, second, micros * 1000, fsp)?;
+        Ok(duration.clear_neg_if_zero())
+    }
+
+    /// The value as a signed number of nanoseconds.
+    pub fn to_nanos(&self) -> i64 {
+        let nanos = self.to_std().0.as_nanos() as i64;
+        if self.neg() {
+            -nanos
+        } else {
+            nanos
         }
-        hour += day.unwrap_or(0) * 24;
-        Duration::with_detail(neg, hour, minute, second, nano, fsp)
+    }
+
+    /// The value as a signed number of microseconds, anything finer is dropped.
+    pub fn to_micros(&self) -> i64 {
+        self.to_nanos() / 1000
+    }
+}
+
+duration_common::impl_parse_prefix!(Duration);
+duration_common::impl_signed_conversions!(Duration);
+#[cfg(feature = "serde")]
+duration_common::impl_serde!(Duration);
+#[cfg(all(test, feature = "serde"))]
+duration_common::serde_round_trip_test!(Duration);
+
+/// Keeps the value down to microseconds, the finest precision of a TIME value.
+impl TryFrom<(StdDuration, bool)> for Duration {
+    type Error = ParseError;
+
+    fn try_from((duration, neg): (StdDuration, bool)) -> Result<Duration> {
+        Duration::from_std(duration, neg, MAX_FSP)
     }
 }
 
@@ -229,4 +419,161 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_from_i64() {
+        let cases = vec![
+            (123456, Ok((false, 12, 34, 56))),
+            (-8385959, Ok((true, 838, 59, 59))),
+            (20190515123456, Ok((false, 12, 34, 56))),
+            (8385960, Err(ParseError::Overflow(0))),
+            (126000, Err(ParseError::MinuteOutOfRange(0))),
+            (20191315123456, Err(ParseError::InvalidDate(0))),
+        ];
+        for (value, expected) in cases {
+            let actual =
+                Duration::from_i64(value, 0).map(|d| (d.neg(), d.hour(), d.minute(), d.second()));
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+        let (duration, len) = Duration::parse_prefix(b"12:34:56abc", 0).unwrap();
+        assert_eq!(
+            (duration.hour(), duration.minute(), duration.second()),
+            (12, 34, 56)
+        );
+        assert_eq!(len, 8);
+        let (duration, len) = Duration::parse_prefix(b"1 12:34:", 0).unwrap();
+        assert_eq!(
+            (duration.hour(), duration.minute(), duration.second()),
+            (36, 34, 0)
+        );
+        assert_eq!(len, 7);
+        assert_eq!(
+            Duration::parse_prefix(b"abc", 0).unwrap_err(),
+            ParseError::InvalidCharacter(0)
+        );
+    }
+
+    #[test]
+    fn test_std_duration() {
+        let duration = Duration::parse(b"-1 2:3:4.567891", 6).unwrap();
+        let (std, neg) = duration.to_std();
+        assert_eq!((std, neg), (StdDuration::new(93784, 567_891_000), true));
+        assert_eq!(Duration::try_from((std, neg)).unwrap().0, duration.0);
+        assert_eq!(duration.to_nanos(), -93_784_567_891_000);
+        assert_eq!(
+            Duration::from_nanos(-93_784_567_891_000, 6).unwrap().0,
+            duration.0
+        );
+        assert_eq!(duration.to_micros(), -93_784_567_891);
+        assert_eq!(
+            Duration::from_micros(-93_784_567_891, 6).unwrap().0,
+            duration.0
+        );
+        assert_eq!(
+            Duration::try_from((StdDuration::from_secs(839 * 3600), false)).unwrap_err(),
+            ParseError::HourOutOfRange(0)
+        );
+    }
+
+    #[test]
+    fn test_bits() {
+        let duration = Duration::parse(b"-1 2:3:4.567891", 6).unwrap();
+        assert_eq!(
+            Duration::from_bits(duration.to_bits()).unwrap().0,
+            duration.0
+        );
+        let micro_bits = duration.to_micro_bits().unwrap();
+        assert_eq!(Duration::from_micro_bits(micro_bits).unwrap().0, duration.0);
+        assert_eq!(
+            Duration::from_bits(7 << 1).unwrap_err(),
+            ParseError::InvalidFsp(7)
+        );
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let duration = Duration::from_parts(true, 26, 3, 4, 567_891, 6).unwrap();
+        assert_eq!(
+            duration.0,
+            Duration::parse(b"-1 2:3:4.567891", 6).unwrap().0
+        );
+        assert_eq!(duration.nano(), 567_891_000);
+        assert_eq!(
+            Duration::from_parts(false, 1, 60, 0, 0, 0).unwrap_err(),
+            ParseError::MinuteOutOfRange(0)
+        );
+        assert_eq!(
+            Duration::from_parts(false, 839, 0, 0, 0, 0).unwrap_err(),
+            ParseError::HourOutOfRange(0)
+        );
+        assert_eq!(
+            Duration::from_parts(true, 838, 59, 59, 0, 6)
+                .unwrap()
+                .to_string(),
+            "-838:59:59.000000"
+        );
+        assert_eq!(
+            Duration::from_parts(false, 838, 59, 59, 1, 6).unwrap_err(),
+            ParseError::HourOutOfRange(0)
+        );
+        assert_eq!(
+            Duration::from_parts(false, 838, 59, 59, 1, 5)
+                .unwrap()
+                .to_string(),
+            "838:59:59.00000"
+        );
+        let max = StdDuration::from_secs(838 * 3600 + 59 * 60 + 59);
+        assert_eq!(
+            Duration::from_std(max + StdDuration::from_micros(1), false, 6).unwrap_err(),
+            ParseError::HourOutOfRange(0)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let cases = vec![
+            ("12:34:56", 0, "12:34:56"),
+            ("-1 1:2:3.123567", 6, "-25:02:03.123567"),
+            ("1:2:3.1235", 3, "01:02:03.124"),
+            ("838:59:59", 2, "838:59:59.00"),
+        ];
+        for (input, fsp, expected) in cases {
+            let duration = Duration::parse(input.as_bytes(), fsp).unwrap();
+            assert_eq!(duration.to_string(), expected);
+            let mut buf = Vec::new();
+            duration.write_to(&mut buf);
+            assert_eq!(buf, expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        for &(s, fsp, mode, len, warnings) in duration_common::testdata::PARSE_WARNINGS {
+            let mut ctx = EvalContext::new(mode);
+            let (_, read) = Duration::parse_prefix_with_ctx(&mut ctx, s.as_bytes(), fsp).unwrap();
+            assert_eq!((read, ctx.warnings()), (len, warnings), "{}", s);
+        }
+        let mut ctx = EvalContext::new(ParseMode::Truncate);
+        let duration = Duration::parse_with_ctx(&mut ctx, b"-1000:00:00.5", 1).unwrap();
+        assert_eq!(duration.to_string(), "-838:59:59.0");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        for &(s, fsp, err) in duration_common::testdata::PARSE_ERRORS {
+            assert_eq!(
+                Duration::parse(s.as_bytes(), fsp).unwrap_err(),
+                err,
+                "{}",
+                s
+            );
+        }
+        assert_eq!(
+            Duration::parse(b"1 12:34.5y", 6).unwrap_err(),
+            ParseError::InvalidCharacter(9)
+        );
+    }
 }